@@ -1,5 +1,6 @@
 use std::net::Ipv4Addr;
 use clap::Parser;
+use multiplayer_fps::data::Team;
 
 
 #[derive(Debug,Parser,Clone)]
@@ -7,14 +8,34 @@ use clap::Parser;
 /// launch the multiplayer fps client
 pub struct Args {
     /// host addr
-    #[arg(long)]
-    pub host: Ipv4Addr,
+    #[arg(long,required_unless_present_any=["demo","host_map"])]
+    pub host: Option<Ipv4Addr>,
+
+    /// host port. with --host-map, port the hosted server listens on, random by default
+    #[arg(long,required_unless_present_any=["demo","host_map"])]
+    pub port: Option<u32>,
 
     /// host port
+    #[arg(long,required_unless_present="demo")]
+    pub nickname: Option<String>,
+
+    /// password of the server, if it asks for one
     #[arg(long)]
-    pub port: u32,
+    pub password: Option<String>,
 
-    /// host port
+    /// team to join in the team modes. the server may pick the other one to keep them balanced
+    #[arg(long,value_enum)]
+    pub team: Option<Team>,
+
+    /// skin to wear. the server gives its default one if it doesn't allow it
     #[arg(long)]
-    pub nickname: String,
-}
\ No newline at end of file
+    pub skin: Option<String>,
+
+    /// replay a demo file instead of joining a server
+    #[arg(long,conflicts_with_all=["host","port","nickname"])]
+    pub demo: Option<String>,
+
+    /// host a server playing this map file and join it. it stops when you quit
+    #[arg(long="host-map",conflicts_with_all=["host","demo"])]
+    pub host_map: Option<String>,
+}
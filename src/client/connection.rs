@@ -1,5 +1,5 @@
-use std::{ net::{SocketAddr, UdpSocket}, sync::{Arc, atomic::AtomicBool, mpsc::{Receiver, Sender, TryRecvError, channel}}, thread, time::Duration};
-use multiplayer_fps::data::{default_addr, Connection, InputData, OutputData};
+use std::{ net::{SocketAddr, UdpSocket}, sync::{Arc, atomic::AtomicBool, mpsc::{Receiver, Sender, TryRecvError, channel}}, thread, time::{Duration, Instant}};
+use crate::{data::{Connection, InputData, OutputData}, transport::{Transport, MAX_DATAGRAM}};
 
 type Error = Box<dyn std::error::Error>;
 
@@ -22,19 +22,27 @@ impl UdpThread {
     }
 }
 
-pub fn connection(server: SocketAddr,nickname: String,timeout: Option<Duration>) -> Result<(Sender<InputData>, Receiver<OutputData>,UdpThread), Error> {
+/// Connects to `server` with `request`, from a new UDP socket.
+pub fn connection(server: SocketAddr,request: Connection,timeout: Option<Duration>) -> Result<(Sender<InputData>, Receiver<OutputData>,UdpThread), Error> {
     let socket = UdpSocket::bind("0.0.0.0:0")?;
-    socket.connect(server)?;
     socket.set_nonblocking(true)?;
     socket.set_read_timeout(timeout)?;
+    connection_over(socket, server, request)
+}
+
+/// Same as `connection`, over any transport, such as a socket of a `MemoryNetwork`.
+///
+/// Datagrams coming from another address than `server` are ignored. The
+/// loop also sends the queued inputs between two reads, so the transport
+/// should be non-blocking or have a short read timeout.
+pub fn connection_over<T: Transport + 'static>(socket: T,server: SocketAddr,request: Connection) -> Result<(Sender<InputData>, Receiver<OutputData>,UdpThread), Error> {
     let killswitch = UdpThread::new();
     let (input_tx, input_rx) = channel::<InputData>();
     let (output_tx, output_rx) = channel::<OutputData>();
 
-    let socket_clone = socket.try_clone()?;
     let kill_switch_clone = killswitch.clone();
     thread::spawn(move  || {
-        if let Err(e) = connection_loop(socket_clone, input_rx, output_tx, nickname,kill_switch_clone) {
+        if let Err(e) = connection_loop(&socket, server, input_rx, output_tx, request,kill_switch_clone) {
             eprintln!("Erreur dans le thread de communication : {e}");
         }
     });
@@ -43,15 +51,16 @@ pub fn connection(server: SocketAddr,nickname: String,timeout: Option<Duration>)
 }
 
 fn connection_loop(
-    socket: UdpSocket,
+    socket: &dyn Transport,
+    server: SocketAddr,
     input_rx: Receiver<InputData>,
     output_tx: Sender<OutputData>,
-    nickname: String,
+    request: Connection,
     kill_switch: UdpThread,
 ) -> Result<(), Error> {
-        let data = InputData::Connection(Connection {addr: default_addr(),nickname});
+        let data = InputData::Connection(request);
         let serialized = serde_json::to_string(&data)?;
-        socket.send(serialized.as_bytes())?;
+        socket.send_to(serialized.as_bytes(), server)?;
     loop {
         if kill_switch.is_dead() {
             break;
@@ -59,21 +68,28 @@ fn connection_loop(
         match input_rx.try_recv() {
             Ok(v) => {
                 let serialized = serde_json::to_string(&v)?;
-                socket.send(serialized.as_bytes())?;
+                socket.send_to(serialized.as_bytes(), server)?;
             },
             Err(TryRecvError::Empty) => (),
             Err(e) => return Err(Box::new(e)),
         }; // peut renvoyer RecvError
 
-        let mut buf = [0u8; 4096];
-        let size = match socket.recv(&mut buf) {
-            Ok(s) => s,
+        let mut buf = [0u8; MAX_DATAGRAM];
+        let size = match socket.recv_from(&mut buf) {
+            Ok((s, from)) if from == server => s,
+            Ok(_) => continue,
             Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => continue,
             Err(e) => return Err(Box::new(e)),
         };
 
+        let received = Instant::now();
         let data = String::from_utf8(buf[..size].to_vec())?;
-        let output = serde_json::from_str::<OutputData>(&data).unwrap_or(OutputData::Unknown);
+        let output = match serde_json::from_str::<OutputData>(&data) {
+            // The round trip ends here, not once the game loop gets to the answer
+            Ok(OutputData::Pong { client, server, .. }) => OutputData::Pong { client, server, received: Some(received) },
+            Ok(output) => output,
+            Err(_) => OutputData::Unknown,
+        };
         let _ = output_tx.send(output);
     }
     Ok(())
@@ -0,0 +1,190 @@
+use std::error::Error;
+
+use multiplayer_fps::{camera::Camera, data::OutputData, demo::{Demo, DemoEvent}, entities::{Players, Skin}, frames::FramesCtrl, Loader};
+use sdl2::{event::Event, keyboard::Keycode, pixels::Color, rect::Rect};
+
+use crate::screen::{draw_minimap, draw_view, load_map, load_skins, window_init, Layout};
+
+/// Milliseconds skipped by one seek.
+const SEEK_STEP: f64 = 5000.0;
+const MIN_SPEED: f64 = 0.25;
+const MAX_SPEED: f64 = 8.0;
+
+/// State of the match at the current time of the demo.
+///
+/// # Fields
+/// - `demo`: The frames being replayed.
+/// - `cursor`: Index of the next frame to apply.
+/// - `time`: Current time of the replay, in milliseconds.
+/// - `speed`: Replay speed factor.
+/// - `paused`: Whether the time is frozen.
+/// - `players`: The players at `time`.
+/// - `loader`: The map played at `time`.
+/// - `skins`: The skins of the server, kept whatever the time.
+struct Playback {
+    demo: Demo,
+    cursor: usize,
+    time: f64,
+    speed: f64,
+    paused: bool,
+    players: Players,
+    loader: Option<Loader>,
+    skins: Vec<Skin>,
+}
+
+impl Playback {
+    fn new(demo: Demo) -> Self {
+        Self { demo, cursor: 0, time: 0.0, speed: 1.0, paused: false, players: Players::new(), loader: None, skins: Vec::new() }
+    }
+
+    /// Moves the time forward by `dt` real seconds, scaled by the speed.
+    ///
+    /// # Returns
+    /// * `Some(Loader)` if the map changed and must be rebuilt.
+    fn advance(&mut self, dt: f64) -> Option<Loader> {
+        if !self.paused {
+            self.time = (self.time + dt * 1000.0 * self.speed).min(self.demo.duration() as f64);
+        }
+        self.apply()
+    }
+
+    /// Jumps to `time`, replaying from the closest snapshot before it.
+    fn seek(&mut self, time: f64) -> Option<Loader> {
+        self.time = time.clamp(0.0, self.demo.duration() as f64);
+        self.cursor = self.demo.snapshot_before(self.time as u64);
+        self.apply()
+    }
+
+    /// Applies every frame up to the current time.
+    fn apply(&mut self) -> Option<Loader> {
+        let mut new_map = None;
+        while let Some(frame) = self.demo.frames.get(self.cursor) {
+            if frame.time as f64 > self.time {
+                break;
+            }
+            match &frame.event {
+                DemoEvent::Snapshot { loader, players } => {
+                    self.players = players.clone();
+                    if self.loader.as_ref() != Some(loader) {
+                        self.loader = Some(loader.clone());
+                        new_map = Some(loader.clone());
+                    }
+                },
+                DemoEvent::Output(OutputData::Update(data)) => {
+                    self.players.update(data);
+                },
+                DemoEvent::Output(OutputData::New(player)) => {
+                    if self.players.get_by_nickname(&player.nickname).is_none() {
+                        self.players.push(player.clone());
+                    }
+                },
+                DemoEvent::Output(OutputData::Skins(skins)) => self.skins = skins.clone(),
+                DemoEvent::Output(OutputData::Message(message)) => println!("[demo {:.1}s] {}", frame.time as f64 / 1000.0, message),
+                DemoEvent::Output(OutputData::Event(event)) => println!("[demo {:.1}s] {}", frame.time as f64 / 1000.0, event),
+                DemoEvent::Output(_) => (),
+            }
+            self.cursor += 1;
+        }
+        new_map
+    }
+
+    /// Nickname of the player following `current` in the list, or `None` after the last one.
+    fn next_target(&self, current: &Option<String>) -> Option<String> {
+        let index = match current {
+            Some(nickname) => self.players.get_by_nickname(nickname).map_or(0, |i| i + 1),
+            None => 0,
+        };
+        self.players.get(index).map(|p| p.nickname.clone())
+    }
+}
+
+/// Replays a demo file in a window, without any network connection.
+///
+/// # Controls
+/// - `P`: pause or resume.
+/// - `Up` / `Down`: double or halve the speed.
+/// - `Left` / `Right`: seek backward or forward.
+/// - `Tab`: follow the next player, then back to the free camera.
+/// - `W` `A` `S` `D` `Q` `E`: move the free camera.
+/// - `Escape`: quit.
+pub fn play(path: &str, title: &str, layout: Layout, target_fps: u32) -> Result<(), Box<dyn Error>> {
+    let demo = Demo::from_file(path)?;
+    println!("replaying {} ({:.1}s)", path, demo.duration() as f64 / 1000.0);
+    println!("P: pause, Up/Down: speed, Left/Right: seek, Tab: follow a player, Escape: quit");
+    let mut playback = Playback::new(demo);
+    let loader = match playback.seek(0.0) {
+        Some(l) => l,
+        None => return Err(format!("demo \"{}\" has no map", path).into()),
+    };
+
+    let sdl = sdl2::init()?;
+    let mut event_pump = sdl.event_pump()?;
+    let window = window_init(title, layout.all.width(), layout.all.height(), sdl)?;
+    let mut canvas = window.into_canvas().accelerated().build()?;
+    let texture_creator = canvas.texture_creator();
+    let (mut map, mut texture_manager) = load_map(&loader, &texture_creator)?;
+    load_skins(&mut texture_manager, &playback.skins);
+    let spawn = playback.players.first().map_or((1.5, 1.5), |p| (p.x, p.y));
+    let mut free_camera = Camera::new(spawn.0, spawn.1, 0.0);
+    let mut follow: Option<String> = None;
+    let mut frame_ctrl = FramesCtrl::init(target_fps);
+    'running: loop {
+        frame_ctrl.start_frame();
+        let mut new_map = None;
+        for event in event_pump.poll_iter() {
+            match event {
+                Event::Quit { .. } | Event::KeyDown { keycode: Some(Keycode::Escape), .. } => break 'running,
+                Event::KeyDown { keycode: Some(Keycode::P), .. } => playback.paused = !playback.paused,
+                Event::KeyDown { keycode: Some(Keycode::Up), .. } => playback.speed = (playback.speed * 2.0).min(MAX_SPEED),
+                Event::KeyDown { keycode: Some(Keycode::Down), .. } => playback.speed = (playback.speed / 2.0).max(MIN_SPEED),
+                Event::KeyDown { keycode: Some(Keycode::Left), .. } => new_map = playback.seek(playback.time - SEEK_STEP),
+                Event::KeyDown { keycode: Some(Keycode::Right), .. } => new_map = playback.seek(playback.time + SEEK_STEP),
+                Event::KeyDown { keycode: Some(Keycode::Tab), .. } => follow = playback.next_target(&follow),
+                _ => (),
+            }
+        }
+        if let Some(loader) = new_map.or_else(|| playback.advance(frame_ctrl.dtime)) {
+            (map, texture_manager) = load_map(&loader, &texture_creator)?;
+            load_skins(&mut texture_manager, &playback.skins);
+        }
+
+        let mut others = playback.players.clone();
+        let followed = follow.as_ref().and_then(|nickname| others.get_by_nickname(nickname));
+        let camera = match followed {
+            Some(index) => {
+                let p = others[index].clone();
+                others.remove(index);
+                Camera::new(p.x, p.y, p.d)
+            },
+            None => {
+                follow = None;
+                free_camera.inputs(&mut event_pump, frame_ctrl.dtime as f32, &map);
+                free_camera
+            },
+        };
+
+        canvas.set_viewport(layout.all);
+        canvas.set_draw_color(Color::BLACK);
+        canvas.clear();
+        draw_view(&mut canvas, &layout, camera, &map, &texture_manager, &others, &[])?;
+        draw_minimap(&mut canvas, &layout, camera, &map, &others, &[])?;
+        draw_timeline(&mut canvas, &layout, &playback)?;
+        canvas.set_viewport(layout.all);
+        canvas.present();
+        frame_ctrl.end_frame();
+    }
+    Ok(())
+}
+
+/// Draws the progress of the replay in the interface zone.
+fn draw_timeline(canvas: &mut sdl2::render::Canvas<sdl2::video::Window>, layout: &Layout, playback: &Playback) -> Result<(), String> {
+    canvas.set_viewport(layout.interface);
+    let (w, h) = (layout.interface.width(), layout.interface.height());
+    canvas.set_draw_color(Color::RGB(30, 30, 30));
+    canvas.fill_rect(Rect::new(0, 0, w, h))?;
+    let duration = playback.demo.duration().max(1) as f64;
+    let progress = ((playback.time / duration) * w as f64) as u32;
+    canvas.set_draw_color(if playback.paused { Color::GRAY } else { Color::CYAN });
+    canvas.fill_rect(Rect::new(0, (h / 2) as i32 - 4, progress.max(1), 8))?;
+    Ok(())
+}
@@ -0,0 +1,284 @@
+use std::{collections::{HashMap, VecDeque}, error::Error, time::{Duration, Instant}};
+
+use multiplayer_fps::{clock::ServerClock, data::{Flag, FlagState, GameEvent, Hit, Item, Loadout, Phase, Projectile}, entities::{Player, Skin, Weapon, MAX_ARMOR, MAX_HEALTH}, world::Map};
+use sdl2::{pixels::Color, rect::Rect, render::{BlendMode, Canvas, TextureCreator}, ttf::Font, video::{Window, WindowContext}};
+
+use crate::{scoreboard::Scoreboard, screen::{text, Layout}};
+
+/// How long the hit marker stays on screen.
+const HIT_MARKER: Duration = Duration::from_millis(200);
+/// How long the screen edges stay red after being hit.
+const DAMAGE_FLASH: Duration = Duration::from_millis(300);
+/// How long an event stays in the kill feed, fading out over its last `FEED_FADE`.
+const FEED_LIFETIME: Duration = Duration::from_secs(6);
+const FEED_FADE: Duration = Duration::from_secs(2);
+/// Events shown at most in the kill feed.
+const FEED_LENGTH: usize = 5;
+
+/// The local player is dead.
+///
+/// # Fields
+/// - `until`: Server time from which it can respawn.
+/// - `delay`: Time it had to wait when it died, if the clock was synchronized.
+/// - `killer`: Nickname of the player who killed it.
+pub struct Death {
+    pub until: u64,
+    pub delay: Option<Duration>,
+    pub killer: Option<String>,
+}
+
+/// State of the local player shown around the view.
+///
+/// # Fields
+/// - `health`: Health left.
+/// - `armor`: Armor left.
+/// - `death`: Set while the local player is dead.
+/// - `scoreboard`: Standing of every player, shown on demand.
+/// - `phase`: Where the match stands.
+/// - `flags`: The flags of the capture-the-flag mode, empty in the other modes.
+/// - `projectiles`: The projectiles in flight.
+/// - `items`: The items of the map.
+/// - `weapons`: The weapons of the server, the default rifle until they are received.
+/// - `skins`: The skins of the server, whose textures are loaded whatever the map.
+/// - `loadout`: The weapons of the local player, predicted between two updates of the server.
+/// - `protected_until`: Server time until which the local player can't be hurt after spawning.
+/// - `protections`: Server time until which each other player can't be hurt, by nickname.
+/// - `feed`: The latest events of the match and when they were received, the newest last.
+/// - `next_shot`: When the weapon in hand can fire again.
+/// - `last_hit`: When a shot of the local player last hit someone.
+/// - `last_damage`: When the local player was last hit.
+/// - `last_killer`: Who made the last fatal hit on the local player.
+pub struct Hud {
+    pub health: u32,
+    pub armor: u32,
+    pub death: Option<Death>,
+    pub scoreboard: Scoreboard,
+    pub phase: Phase,
+    pub flags: Vec<Flag>,
+    pub projectiles: Vec<Projectile>,
+    pub items: Vec<Item>,
+    pub weapons: Vec<Weapon>,
+    pub skins: Vec<Skin>,
+    pub loadout: Loadout,
+    pub protected_until: u64,
+    pub protections: HashMap<String, u64>,
+    feed: VecDeque<(Instant, GameEvent)>,
+    next_shot: Instant,
+    last_hit: Option<Instant>,
+    last_damage: Option<Instant>,
+    last_killer: Option<String>,
+}
+
+impl Hud {
+    pub fn new(player: &Player) -> Self {
+        let weapons = vec![Weapon { starting: true, ..Weapon::default() }];
+        let loadout = Loadout::new(&weapons);
+        Self { health: player.health, armor: player.armor, death: None, scoreboard: Scoreboard::new(), phase: Phase::default(), flags: Vec::new(), projectiles: Vec::new(), items: Vec::new(), weapons, skins: Vec::new(), loadout, protected_until: 0, protections: HashMap::new(), feed: VecDeque::new(), next_shot: Instant::now(), last_hit: None, last_damage: None, last_killer: None }
+    }
+
+    /// The weapon in hand.
+    pub fn weapon(&self) -> Option<&Weapon> {
+        self.weapons.get(self.loadout.weapon)
+    }
+
+    /// Applies the loadout sent by the server, `ready_at` being converted with `clock`.
+    pub fn set_loadout(&mut self, loadout: Loadout, clock: &ServerClock) {
+        self.next_shot = Instant::now() + clock.until(loadout.ready_at).unwrap_or_default();
+        self.loadout = loadout;
+    }
+
+    /// Forgets the projectiles past their range or in a wall, in case their
+    /// explosion was lost on the way.
+    pub fn prune_projectiles(&mut self, map: &Map, clock: &ServerClock) {
+        let now = match clock.server_ms() {
+            Some(t) => t,
+            None => return,
+        };
+        self.projectiles.retain(|p| {
+            let (x, y) = p.position_after(p.traveled_at(now));
+            now <= p.until && map.is_wall(x.floor() as i32, y.floor() as i32) == Some(false)
+        });
+    }
+
+    /// Whether the local player is under spawn protection.
+    pub fn protected(&self, clock: &ServerClock) -> bool {
+        clock.server_ms().is_some_and(|now| now < self.protected_until)
+    }
+
+    /// Whether the weapon in hand is loaded and ready to fire.
+    pub fn can_shoot(&self) -> bool {
+        self.loadout.ammo() > 0 && Instant::now() >= self.next_shot
+    }
+
+    /// Takes a shot from the magazine the way the server does, reloading an
+    /// emptied one. Firing ends the spawn protection.
+    pub fn fired(&mut self) {
+        self.protected_until = 0;
+        let (ammo, interval, reload) = match self.weapon() {
+            Some(w) => (w.ammo, w.fire_interval(), w.reload),
+            None => return,
+        };
+        let index = self.loadout.weapon;
+        self.loadout.give(index, self.loadout.ammo().saturating_sub(1));
+        self.next_shot = Instant::now() + Duration::from_millis(interval);
+        if self.loadout.ammo() == 0 {
+            self.loadout.give(index, ammo);
+            self.next_shot = Instant::now() + Duration::from_millis(reload);
+        }
+    }
+
+    /// Applies a `Hit` event involving the player named `nickname`.
+    pub fn hit(&mut self, hit: &Hit, nickname: &str) {
+        if hit.shooter == nickname {
+            self.last_hit = Some(Instant::now());
+        }
+        if hit.target == nickname {
+            self.last_damage = Some(Instant::now());
+            (self.health, self.armor) = (hit.health, hit.armor);
+            if hit.fatal {
+                self.last_killer = Some(hit.shooter.clone());
+            }
+        }
+    }
+
+    /// Adds `event` to the kill feed, dropping the oldest one if it is full.
+    pub fn event(&mut self, event: GameEvent) {
+        if self.feed.len() >= FEED_LENGTH {
+            self.feed.pop_front();
+        }
+        self.feed.push_back((Instant::now(), event));
+    }
+
+    /// Enters the dead state until the server time `until`.
+    pub fn died(&mut self, until: u64, clock: &ServerClock) {
+        self.death = Some(Death { until, delay: clock.until(until), killer: self.last_killer.take() });
+    }
+
+    /// Leaves the dead state, the server respawned the local player with full health.
+    pub fn respawned(&mut self) {
+        self.death = None;
+        (self.health, self.armor) = (MAX_HEALTH, 0);
+    }
+
+    /// Whether the match is over, the results being shown until the next map.
+    pub fn intermission(&self) -> bool {
+        matches!(self.phase, Phase::Intermission { .. })
+    }
+
+    /// Tells if the respawn delay is over. Assumed so if the clock isn't synchronized,
+    /// the server refusing early requests anyway.
+    pub fn can_respawn(&self, clock: &ServerClock) -> bool {
+        self.death.as_ref().is_some_and(|death| clock.until(death.until).is_none_or(|left| left.is_zero()))
+    }
+
+    /// Draws the health and armor bars, the weapon in hand, the match timer
+    /// and where the flags are in the interface zone, then the hit marker and the damage flash over the view.
+    ///
+    /// While dead, the view is darkened and a bar counts down until the
+    /// respawn is allowed, turning green once it is. During the intermission
+    /// the winner is announced on top of the view. The time left under spawn
+    /// protection is shown in its top left corner, and the kill feed in its top
+    /// right corner.
+    pub fn draw(&self, canvas: &mut Canvas<Window>, layout: &Layout, clock: &ServerClock, font: &Font, texture_creator: &TextureCreator<WindowContext>) -> Result<(), Box<dyn Error>> {
+        canvas.set_viewport(layout.interface);
+        let (w, h) = (layout.interface.width(), layout.interface.height());
+        canvas.set_draw_color(Color::CYAN);
+        canvas.fill_rect(Rect::new(0, 0, w, h))?;
+        let bar = w.saturating_sub(200);
+        canvas.set_draw_color(Color::RGB(60, 0, 0));
+        canvas.fill_rect(Rect::new(20, (h / 3) as i32 - 12, bar, 24))?;
+        canvas.set_draw_color(Color::RED);
+        canvas.fill_rect(Rect::new(20, (h / 3) as i32 - 12, (bar * self.health.min(MAX_HEALTH) / MAX_HEALTH).max(1), 24))?;
+        canvas.set_draw_color(Color::RGB(0, 0, 60));
+        canvas.fill_rect(Rect::new(20, (2 * h / 3) as i32 - 12, bar, 24))?;
+        canvas.set_draw_color(Color::BLUE);
+        canvas.fill_rect(Rect::new(20, (2 * h / 3) as i32 - 12, (bar * self.armor.min(MAX_ARMOR) / MAX_ARMOR).max(1), 24))?;
+        let timer = match &self.phase {
+            Phase::Warmup { until: None } => "warmup".to_string(),
+            Phase::Warmup { until: Some(time) } => format!("starts in {}", countdown(clock, *time)),
+            Phase::InProgress { until: Some(time), .. } => countdown(clock, *time),
+            Phase::InProgress { until: None, frag_limit } => frag_limit.map_or("no limit".to_string(), |limit| format!("first to {}", limit)),
+            Phase::Intermission { .. } => "match over".to_string(),
+        };
+        text(canvas, font, texture_creator, &timer, (bar as i32 + 40, (h / 2) as i32 - 8), Color::BLACK)?;
+        if let Some(weapon) = self.weapon() {
+            let state = if Instant::now() < self.next_shot && self.loadout.ammo() == weapon.ammo {
+                "reloading".to_string()
+            } else {
+                format!("{}/{}", self.loadout.ammo(), weapon.ammo)
+            };
+            text(canvas, font, texture_creator, &format!("{} {}", weapon.name, state), (bar as i32 + 40, 12), Color::BLACK)?;
+        }
+        for (i, flag) in self.flags.iter().enumerate() {
+            let state = match &flag.state {
+                FlagState::AtBase => "at base".to_string(),
+                FlagState::Carried(nickname) => format!("taken by {}", nickname),
+                FlagState::Dropped { .. } => "dropped".to_string(),
+            };
+            let at = (bar as i32 + 40, (h / 2) as i32 + 16 + 20 * i as i32);
+            text(canvas, font, texture_creator, &format!("{} flag: {}", flag.team, state), at, flag.team.color())?;
+        }
+
+        canvas.set_viewport(layout.render);
+        let (cx, cy) = ((layout.render.width() / 2) as i32, (layout.render.height() / 2) as i32);
+        if self.last_hit.is_some_and(|t| t.elapsed() < HIT_MARKER) {
+            canvas.set_draw_color(Color::WHITE);
+            for (dx, dy) in [(-1, -1), (1, -1), (-1, 1), (1, 1)] {
+                canvas.draw_line((cx + dx * 6, cy + dy * 6), (cx + dx * 14, cy + dy * 14))?;
+            }
+        }
+        if let Some(death) = &self.death {
+            let (w, h) = (layout.render.width(), layout.render.height());
+            canvas.set_blend_mode(BlendMode::Blend);
+            canvas.set_draw_color(Color::RGBA(60, 0, 0, 140));
+            canvas.fill_rect(Rect::new(0, 0, w, h))?;
+            canvas.set_blend_mode(BlendMode::None);
+            let left = match (clock.until(death.until), death.delay) {
+                (Some(left), Some(delay)) if !delay.is_zero() => left.as_secs_f32() / delay.as_secs_f32(),
+                _ => 0.0,
+            };
+            let bar = w / 2;
+            let (x, y) = ((w / 4) as i32, (h * 2 / 3) as i32);
+            canvas.set_draw_color(Color::GRAY);
+            canvas.fill_rect(Rect::new(x, y, bar, 16))?;
+            canvas.set_draw_color(if self.can_respawn(clock) { Color::GREEN } else { Color::WHITE });
+            canvas.fill_rect(Rect::new(x, y, ((bar as f32 * (1.0 - left.min(1.0))) as u32).max(1), 16))?;
+        }
+        if let Some(left) = clock.until(self.protected_until).filter(|_| self.protected(clock)) {
+            text(canvas, font, texture_creator, &format!("protected {:.1}s", left.as_secs_f32()), (16, 16), Color::GREEN)?;
+        }
+        let feed = self.feed.iter().filter(|(at, _)| at.elapsed() < FEED_LIFETIME);
+        for (i, (at, event)) in feed.enumerate() {
+            let left = FEED_LIFETIME.saturating_sub(at.elapsed());
+            let alpha = (255.0 * (left.as_secs_f32() / FEED_FADE.as_secs_f32()).min(1.0)) as u8;
+            let at = (layout.render.width() as i32 - 280, 16 + 20 * i as i32);
+            text(canvas, font, texture_creator, &event.to_string(), at, Color::RGBA(255, 255, 255, alpha))?;
+        }
+        if let Phase::Intermission { winner, until } = &self.phase {
+            let title = match winner {
+                Some(nickname) => format!("{} wins the match", nickname),
+                None => "draw".to_string(),
+            };
+            let x = (layout.render.width() / 2) as i32 - 120;
+            let y = layout.render.height() as i32 - 80;
+            text(canvas, font, texture_creator, &title, (x, y), Color::YELLOW)?;
+            text(canvas, font, texture_creator, &format!("next map in {}", countdown(clock, *until)), (x, y + 24), Color::WHITE)?;
+        }
+        if self.last_damage.is_some_and(|t| t.elapsed() < DAMAGE_FLASH) {
+            let (w, h) = (layout.render.width(), layout.render.height());
+            canvas.set_draw_color(Color::RED);
+            for rect in [Rect::new(0, 0, w, 8), Rect::new(0, h as i32 - 8, w, 8), Rect::new(0, 0, 8, h), Rect::new(w as i32 - 8, 0, 8, h)] {
+                canvas.fill_rect(rect)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Minutes and seconds left until the server time `time`, `--:--` if the clock isn't synchronized yet.
+fn countdown(clock: &ServerClock, time: u64) -> String {
+    match clock.until(time) {
+        Some(left) => format!("{}:{:02}", left.as_secs() / 60, left.as_secs() % 60),
+        None => "--:--".to_string(),
+    }
+}
@@ -1,6 +1,7 @@
+use crate::hud::Hud;
 use std::{net::{SocketAddr, UdpSocket}, sync::mpsc::{Receiver, Sender, TryRecvError}, time::{Duration, Instant}};
 
-use multiplayer_fps::{camera::Camera, data::{default_addr, InputData, OutputData, Update}, entities::{Player, Players}, Loader};
+use multiplayer_fps::{camera::Camera, clock::ServerClock, data::{default_addr, InputData, OutputData, Status, Update}, entities::{Player, Players}, Loader};
 
 type Error = Box<dyn std::error::Error>;
 
@@ -11,6 +12,9 @@ pub fn on_connection(data: &Receiver<OutputData>) -> Result<(Player, Players, Lo
     while start.elapsed() < timeout {
         match data.try_recv() {
             Ok(OutputData::Connecting(v)) => return Ok(v),
+            Ok(OutputData::AccessDeny(deny)) => {
+                return Err(format!("access denied: {}", deny.reason).into());
+            }
             Ok(OutputData::None) => {
                 return Err("No response received from server.".into());
             }
@@ -40,31 +44,118 @@ pub fn disconnection(socket: &mut UdpSocket,server: SocketAddr) -> Result<(),Err
     Ok(())
 }
 
+/// Asks the server to respawn the local player.
+pub fn respawn(tx: &Sender<InputData>) -> Result<(),Error> {
+    tx.send(InputData::Respawn { addr: default_addr() })?;
+    Ok(())
+}
+
+/// Asks the server to put in hand the weapon of index `weapon`.
+pub fn switch(tx: &Sender<InputData>,weapon: usize) -> Result<(),Error> {
+    tx.send(InputData::Switch { addr: default_addr(), weapon })?;
+    Ok(())
+}
+
+/// Asks the server to refill the magazine of the weapon in hand.
+pub fn reload(tx: &Sender<InputData>) -> Result<(),Error> {
+    tx.send(InputData::Reload { addr: default_addr() })?;
+    Ok(())
+}
+
 pub fn shoot(tx: &Sender<InputData>,camera: Camera,nickname: &str) -> Result<(),Error> {
     let data = InputData::Shoot(Update::new(default_addr(), nickname.to_string(), camera.xyd()));
     tx.send(data)?;
     Ok(())
 }
 
-pub fn update(tx: &Sender<InputData>,rx: &Receiver<OutputData>,camera: &mut Camera,nickname: &str,others: &mut Players) -> Result<(),Error> {
-    let data = InputData::Update(Update::new(default_addr(), nickname.to_string(), camera.xyd()));
-    tx.send(data)?;
-    let output = match rcv(rx)? {
-        Some(v) => v,
-        None => return Ok(()),
-    };
-    match output {
-        OutputData::Update(data) => {
-            if None == others.update(&data) {
-                if nickname == &data.nickname {
-                    camera.update(&data);
+/// Sends the camera position and applies every message the server sent since the last frame.
+///
+/// Pings are also sent to keep `clock` in sync with the server.
+///
+/// # Returns
+/// * `Ok(Some(Loader))` when the server switched maps: the camera and `others`
+///   are already moved, the caller must rebuild its map and textures.
+/// * `Ok(None)` otherwise.
+pub fn update(tx: &Sender<InputData>,rx: &Receiver<OutputData>,camera: &mut Camera,nickname: &str,others: &mut Players,clock: &mut ServerClock,hud: &mut Hud) -> Result<Option<Loader>,Error> {
+    // A dead player's inputs are ignored by the server until it respawns
+    if hud.death.is_none() {
+        let data = InputData::Update(Update::new(default_addr(), nickname.to_string(), camera.xyd()));
+        tx.send(data)?;
+    }
+    if clock.ping_due() {
+        tx.send(InputData::Ping { addr: default_addr(), time: clock.local_ms() })?;
+    }
+    // Everything is applied at once, a backlog would delay the whole state of the game
+    while let Some(output) = rcv(rx)? {
+        match output {
+            OutputData::Update(data) => {
+                if None == others.update(&data) {
+                    if nickname == &data.nickname {
+                        camera.update(&data);
+                        match data.status {
+                            Some(Status::Dead(until)) => hud.died(until, clock),
+                            // The server only sends players their own state when it respawns them
+                            Some(Status::Alive) => hud.respawned(),
+                            _ => (),
+                        }
+                    }
                 }
-            }
-        },
-        OutputData::New(data) => others.push(data),
-        _ => (),
+            },
+            OutputData::New(data) => match others.get_by_nickname(&data.nickname) {
+                Some(index) => others.players[index] = data,
+                None => others.push(data),
+            },
+            OutputData::Hide(nickname) => {
+                if let Some(index) = others.get_by_nickname(&nickname) {
+                    others.remove(index);
+                }
+            },
+            OutputData::MapChange((player, players, loader)) => {
+                *camera = Camera::new(player.x, player.y, player.d);
+                // The server brings the dead players back on the new map, unless it
+                // runs out of free spawn points
+                match player.status {
+                    Status::Alive => hud.respawned(),
+                    Status::Dead(until) => hud.died(until, clock),
+                    _ => (),
+                }
+                (hud.health, hud.armor) = (player.health, player.armor);
+                *others = players;
+                hud.projectiles.clear();
+                return Ok(Some(loader));
+            },
+            OutputData::Message(message) => println!("[server] {}", message),
+            OutputData::Event(event) => hud.event(event),
+            OutputData::Ping(time) => tx.send(InputData::Pong { addr: default_addr(), time })?,
+            OutputData::Pong { client, server, received } => clock.pong(client, server, received.unwrap_or_else(Instant::now)),
+            OutputData::Hit(hit) => hud.hit(&hit, nickname),
+            OutputData::Scores(scores) => hud.scoreboard.set(scores),
+            OutputData::Phase(phase) => hud.phase = phase,
+            OutputData::Flags(flags) => hud.flags = flags,
+            OutputData::Weapons(weapons) => hud.weapons = weapons,
+            OutputData::Skins(skins) => hud.skins = skins,
+            OutputData::Items(items) => hud.items = items,
+            OutputData::ItemState { index, back_at } => {
+                if let Some(item) = hud.items.get_mut(index) {
+                    item.back_at = back_at;
+                }
+            },
+            OutputData::Vitals { health, armor } => (hud.health, hud.armor) = (health, armor),
+            OutputData::Protection { nickname: target, until } => {
+                if target == nickname {
+                    hud.protected_until = until;
+                } else {
+                    hud.protections.insert(target, until);
+                }
+            },
+            OutputData::Projectile(projectile) => hud.projectiles.push(projectile),
+            OutputData::Explosion { id, .. } => hud.projectiles.retain(|p| p.id != id),
+            OutputData::Loadout(loadout) => hud.set_loadout(loadout, clock),
+            OutputData::AccessDeny(deny) => return Err(format!("disconnected by the server: {}", deny.reason).into()),
+            _ => (),
+        }
     }
-    Ok(())
+    Ok(None)
 }
 
 pub fn rcv<T>(rx: &Receiver<T>) -> Result<Option<T>,Error> {
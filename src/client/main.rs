@@ -1,19 +1,22 @@
 mod args;
 use args::Args;
 use clap::Parser;
-use multiplayer_fps::{camera::Camera, display::Display, entities::Entity, frames::FramesCtrl, resources::TextureManager, world::{Map, Minimap}};
+use multiplayer_fps::{camera::Camera, clock::ServerClock, data::{default_addr, Connection}, frames::FramesCtrl};
 
+mod demo;
+mod hud;
 mod logic;
+mod scoreboard;
 mod screen;
-mod connection;
-use connection::connection;
+use multiplayer_fps::client::connection::connection;
+use multiplayer_fps::server::instance::Instance;
 
 
-use std::{error::Error, net::SocketAddr, time::{Duration, Instant}};
-use sdl2::{EventPump, event::Event, pixels::Color, rect::{FPoint, Rect}};
+use std::{error::Error, net::{Ipv4Addr, SocketAddr}, time::{Duration, Instant}};
+use sdl2::{EventPump, event::Event, pixels::Color};
 use sdl2::keyboard::Keycode;
 
-use crate::{logic::{on_connection, shoot, update}, screen::window_init};
+use crate::{hud::Hud, logic::{on_connection, reload, respawn, shoot, switch, update}, screen::{blinking, draw_minimap, draw_view, flag_sprites, item_sprites, load_font, load_map, load_skins, projectile_sprites, window_init, Layout}};
 
 const WIN_TITLE: &str = "multiplayer fps";
 const SCREEN_WIDTH: u32 = 1080;
@@ -21,15 +24,25 @@ const SCREEN_HEIGHT: u32 = (SCREEN_WIDTH as f32 * 0.8) as u32;
 const HUD_HEIGHT: u32 = SCREEN_WIDTH - SCREEN_HEIGHT;
 
 const TARGET_FPS: u32 = 60;
+/// Simulation ticks per second of a hosted server.
+const HOST_TICK_RATE: u32 = 30;
+/// Time between two respawn requests while the key is held.
+const RESPAWN_RETRY: Duration = Duration::from_millis(500);
 
 
-fn event(e:&mut EventPump) -> u32{
+/// Keys selecting a weapon, by index in the weapons of the server.
+const WEAPON_KEYS: [Keycode; 9] = [Keycode::Num1, Keycode::Num2, Keycode::Num3, Keycode::Num4, Keycode::Num5, Keycode::Num6, Keycode::Num7, Keycode::Num8, Keycode::Num9];
+
+
+/// Polls the pending events, pushing the keys pressed since the last call to `pressed`.
+fn event(e:&mut EventPump,pressed: &mut Vec<Keycode>) -> u32{
     for event in e.poll_iter() {
         match event {
             Event::Quit {..} |
             Event::KeyDown { keycode: Some(Keycode::Escape), .. } => {
                 return 1;
             },
+            Event::KeyDown { keycode: Some(key), repeat: false, .. } => pressed.push(key),
             _ => {}
         }
     }
@@ -37,77 +50,124 @@ fn event(e:&mut EventPump) -> u32{
 }
 
 fn main() -> Result<(),Box<dyn Error>> {
-    let all_screen = Rect::new(0, 0, SCREEN_WIDTH, SCREEN_HEIGHT + HUD_HEIGHT);
-    let render_zone = Rect::new(0, 0, SCREEN_WIDTH, SCREEN_HEIGHT);
-    let minimap_zone = Rect::new(0, SCREEN_HEIGHT as i32 + 1 , HUD_HEIGHT, HUD_HEIGHT);
-    let interface_zone = Rect::new(HUD_HEIGHT as i32+ 1, SCREEN_HEIGHT as i32 + 1, SCREEN_WIDTH - HUD_HEIGHT, HUD_HEIGHT);
+    let layout = Layout::new(SCREEN_WIDTH, SCREEN_HEIGHT, HUD_HEIGHT);
 
     let args = Args::parse();
-    let server: SocketAddr = format!("{}:{}",args.host,args.port).parse()?;
-    let (tx,rx,udp_thread) = connection(server,args.nickname,Some(Duration::from_secs(40)))?;
+    if let Some(path) = &args.demo {
+        return demo::play(path, WIN_TITLE, layout, TARGET_FPS);
+    }
+    // Stops the hosted server when dropped, once the client quits
+    let mut hosted = None;
+    let (server, nickname): (SocketAddr, String) = match (args.host_map, args.host, args.port, args.nickname) {
+        (Some(map), _, port, Some(n)) => {
+            let instance = Instance::new(port.unwrap_or(0), HOST_TICK_RATE, map).run()?;
+            println!("hosting a server on port {}", instance.addr.port());
+            let server = SocketAddr::from((Ipv4Addr::LOCALHOST, instance.addr.port()));
+            hosted = Some(instance);
+            (server, n)
+        },
+        (None, Some(h), Some(p), Some(n)) => (format!("{}:{}",h,p).parse()?, n),
+        _ => return Err("--host, --port and --nickname are required to join a server".into()),
+    };
+    let request = Connection { addr: default_addr(), nickname, password: args.password, team: args.team, skin: args.skin };
+    let (tx,rx,udp_thread) = connection(server,request,Some(Duration::from_secs(40)))?;
     let (player,mut others,map_loader) = on_connection(&rx)?;
+    let mut hud = Hud::new(&player);
     let nickname = player.nickname;
 
     let sdl = sdl2::init()?;
     let mut event_pump = sdl.event_pump()?;
-    let window = window_init(WIN_TITLE, SCREEN_WIDTH, SCREEN_HEIGHT + HUD_HEIGHT, sdl)?;
+    let window = window_init(WIN_TITLE, layout.all.width(), layout.all.height(), sdl)?;
     let mut canvas = window.clone().into_canvas().accelerated().build()?;
     let texture_creator = canvas.texture_creator();
+    let ttf = sdl2::ttf::init()?;
+    let font = load_font(&map_loader, &ttf)?;
 
-    let mut texture_manager = TextureManager::new(&texture_creator);
-    let textures = map_loader.get_resources().textures()?;
-    let textures_ref: std::collections::HashMap<&str, &str> = textures.iter()
-        .map(|(k, v)| (k.as_str(), v.as_str()))
-        .collect();
-    texture_manager.load_from_map(textures_ref)?;
-    let map = Map::from(&map_loader);
+    let (mut map,mut texture_manager) = load_map(&map_loader, &texture_creator)?;
     let mut camera = Camera::new(player.x, player.y, player.d);
     let mut buff_cam_pos: (f32,f32) = camera.position;
     let mut frame_ctrl = FramesCtrl::init(TARGET_FPS);
-    let mut shoot_cooldown = Instant::now();
+    let mut last_respawn = Instant::now();
+    let mut clock = ServerClock::new();
+    let mut last_title = Instant::now();
+    let mut loaded_skins = Vec::new();
     loop {
-        canvas.set_viewport(all_screen);
+        canvas.set_viewport(layout.all);
         canvas.set_draw_color(Color::BLACK);
         canvas.clear();
-        canvas.set_viewport(render_zone);
         frame_ctrl.start_frame();
-        camera.inputs(&mut event_pump, frame_ctrl.dtime as f32,&map);
-        if event_pump.keyboard_state().is_scancode_pressed(sdl2::keyboard::Scancode::Space) && shoot_cooldown.elapsed() >= Duration::from_secs(1) {
-            shoot(&tx, camera, &nickname)?;
-            shoot_cooldown = Instant::now();
+        let space = event_pump.keyboard_state().is_scancode_pressed(sdl2::keyboard::Scancode::Space);
+        let tab = event_pump.keyboard_state().is_scancode_pressed(sdl2::keyboard::Scancode::Tab);
+        match &hud.death {
+            Some(death) => {
+                // Death camera: stay in place, facing the killer while it is in view
+                if let Some(killer) = death.killer.as_ref().and_then(|k| others.get_by_nickname(k)) {
+                    let killer = &others[killer];
+                    camera.direction = (killer.y - camera.position.1).atan2(killer.x - camera.position.0);
+                }
+                if space && hud.can_respawn(&clock) && last_respawn.elapsed() >= RESPAWN_RETRY {
+                    respawn(&tx)?;
+                    last_respawn = Instant::now();
+                }
+            },
+            None => {
+                camera.inputs(&mut event_pump, frame_ctrl.dtime as f32,&map);
+                if space && hud.can_shoot() {
+                    shoot(&tx, camera, &nickname)?;
+                    hud.fired();
+                }
+            },
         }
-        match event(&mut event_pump) {
+        let mut pressed = Vec::new();
+        match event(&mut event_pump, &mut pressed) {
             1 => break,
             _ => (),
         }
-        let mut rays = camera.cast_rays(map.clone(), SCREEN_WIDTH);
-        rays.display(&mut canvas, Some(&texture_manager))?;
-        let mut render_datas = vec![];
-        for other in others.iter() {
-            render_datas.push(other.into_render(camera, &map,&rays));
+        for key in pressed {
+            if key == Keycode::R {
+                reload(&tx)?;
+            } else if let Some(weapon) = WEAPON_KEYS.iter().position(|k| *k == key).filter(|w| hud.loadout.owns(*w)) {
+                switch(&tx, weapon)?;
+                hud.loadout.weapon = weapon;
+            }
         }
-        render_datas.sort();
-        for mut rd in render_datas {
-            rd.display(&mut canvas, Some(&texture_manager))?;
+        if loaded_skins != hud.skins {
+            load_skins(&mut texture_manager, &hud.skins);
+            loaded_skins = hud.skins.clone();
         }
+        hud.prune_projectiles(&map, &clock);
+        let mut sprites = flag_sprites(&hud.flags, &others);
+        sprites.extend(item_sprites(&hud.items));
+        if let Some(time) = clock.server_ms() {
+            sprites.extend(projectile_sprites(&hud.projectiles, time));
+        }
+        draw_view(&mut canvas, &layout, camera, &map, &texture_manager, &blinking(&others, &hud.protections, clock.server_ms()), &sprites)?;
         if camera.position != buff_cam_pos {
             buff_cam_pos = camera.position;
         }
-        canvas.set_viewport(interface_zone);
-        canvas.set_draw_color(Color::CYAN);
-        canvas.fill_rect(Rect::new(0, 0, 1280, 1000))?;
+        hud.draw(&mut canvas, &layout, &clock, &font, &texture_creator)?;
+        if tab || hud.intermission() {
+            hud.scoreboard.draw(&mut canvas, &layout, &font, &texture_creator, &nickname)?;
+        }
 
-        canvas.set_viewport(minimap_zone);
-        let mut minimap = Minimap::new(&map, &FPoint::new(camera.position.0, camera.position.1), Color::GRAY, Color::BLACK);
-        minimap.set_others(others.into_coordinates());
-        minimap.set_target_pinpoint(Some(Color::YELLOW));
-        minimap.display::<()>(&mut canvas, None)?;
+        draw_minimap(&mut canvas, &layout, camera, &map, &others, &hud.flags)?;
 
-        canvas.set_viewport(all_screen);
+        canvas.set_viewport(layout.all);
         canvas.present();
-        update(&tx, &rx,&mut camera, &nickname,&mut others)?;
+        if last_title.elapsed() >= Duration::from_secs(1) {
+            last_title = Instant::now();
+            if let Some(rtt) = clock.rtt() {
+                canvas.window_mut().set_title(&format!("{} - {} ms", WIN_TITLE, rtt.as_millis()))?;
+            }
+        }
+        if let Some(loader) = update(&tx, &rx,&mut camera, &nickname,&mut others,&mut clock,&mut hud)? {
+            (map,texture_manager) = load_map(&loader, &texture_creator)?;
+            load_skins(&mut texture_manager, &hud.skins);
+            buff_cam_pos = camera.position;
+        }
         frame_ctrl.end_frame();
     }
     udp_thread.kill();
+    drop(hosted);
     Ok(())
 }
\ No newline at end of file
@@ -1,3 +1 @@
-// pub mod running;
-// pub use running::run;
-// mod triggered_thead;
\ No newline at end of file
+pub mod connection;
@@ -0,0 +1,78 @@
+use std::error::Error;
+
+use multiplayer_fps::data::Standings;
+use sdl2::{pixels::Color, rect::Rect, render::{BlendMode, Canvas, TextureCreator}, ttf::Font, video::{Window, WindowContext}};
+
+use crate::screen::{text, Layout};
+
+/// Left edge of each column in the panel, and its title.
+const COLUMNS: [(i32, &str); 6] = [(16, "player"), (260, "score"), (340, "kills"), (420, "deaths"), (510, "K/D"), (590, "ping")];
+/// Height of a line of the panel.
+const LINE_HEIGHT: i32 = 24;
+
+/// Standing of every player and team, as last sent by the server.
+#[derive(Default)]
+pub struct Scoreboard {
+    standings: Standings,
+}
+
+impl Scoreboard {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replaces the standings, already sorted by the server.
+    pub fn set(&mut self, standings: Standings) {
+        self.standings = standings;
+    }
+
+    /// Draws the standings in a panel over the view, the local player's line in yellow.
+    ///
+    /// In the team modes, the team scores come first and every player is
+    /// marked with the color of its team.
+    pub fn draw(&self, canvas: &mut Canvas<Window>, layout: &Layout, font: &Font, texture_creator: &TextureCreator<WindowContext>, nickname: &str) -> Result<(), Box<dyn Error>> {
+        canvas.set_viewport(layout.render);
+        let width = 680.min(layout.render.width());
+        let teams = !self.standings.teams.is_empty();
+        let lines = self.standings.players.len() + 1 + teams as usize;
+        let height = (LINE_HEIGHT * lines as i32 + 16) as u32;
+        let x = (layout.render.width() - width) as i32 / 2;
+        let y = 40;
+        canvas.set_blend_mode(BlendMode::Blend);
+        canvas.set_draw_color(Color::RGBA(0, 0, 0, 180));
+        canvas.fill_rect(Rect::new(x, y, width, height))?;
+        canvas.set_blend_mode(BlendMode::None);
+
+        let mut line = y + 8;
+        if teams {
+            for (i, (team, score)) in self.standings.teams.iter().enumerate() {
+                text(canvas, font, texture_creator, &format!("{} {}", team, score), (x + COLUMNS[0].0 + i as i32 * 160, line), team.color())?;
+            }
+            line += LINE_HEIGHT;
+        }
+        for (left, title) in COLUMNS {
+            text(canvas, font, texture_creator, title, (x + left, line), Color::GRAY)?;
+        }
+        for score in &self.standings.players {
+            line += LINE_HEIGHT;
+            if let Some(team) = score.team {
+                canvas.set_draw_color(team.color());
+                canvas.fill_rect(Rect::new(x + 4, line + 4, 8, 8))?;
+            }
+            let color = if score.nickname == nickname { Color::YELLOW } else { Color::WHITE };
+            let ping = if score.bot { "bot".to_string() } else { format!("{} ms", score.ping) };
+            let cells = [
+                score.nickname.clone(),
+                score.stats.score().to_string(),
+                score.stats.kills.to_string(),
+                score.stats.deaths.to_string(),
+                format!("{:.2}", score.stats.ratio()),
+                ping,
+            ];
+            for ((left, _), cell) in COLUMNS.iter().zip(cells) {
+                text(canvas, font, texture_creator, &cell, (x + left, line), color)?;
+            }
+        }
+        Ok(())
+    }
+}
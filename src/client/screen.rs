@@ -1,12 +1,168 @@
-use sdl2::{ video::Window, Sdl};
-type Error = Box<dyn std::error::Error>;
+use std::{collections::HashMap, error::Error};
+
+use multiplayer_fps::{camera::Camera, data::{Flag, Item, Projectile}, display::Display, entities::{Entity, Players, RenderData, Skin}, resources::TextureManager, world::{Map, Minimap}, Loader};
+use sdl2::{pixels::Color, rect::{FPoint, Rect}, render::{Canvas, TextureCreator}, ttf::{Font, Sdl2TtfContext}, video::{Window, WindowContext}, Sdl};
+
+/// Font used when the map doesn't declare any.
+const DEFAULT_FONT: (&str, u16) = ("assets/fonts/ProggyCleanCENerdFontMono-Regular.ttf", 16);
+/// Milliseconds a player under spawn protection stays shown, then hidden, while blinking.
+const BLINK: u64 = 150;
 
 /// init and setup the window
-pub fn window_init(title: &str,width: u32,height: u32,sdl: Sdl) -> Result<Window,Error> {
+pub fn window_init(title: &str,width: u32,height: u32,sdl: Sdl) -> Result<Window,Box<dyn Error>> {
     let video_subsys = sdl.video()?;
     let window = video_subsys.window(title, width, height)
         .opengl()
         .position_centered()
         .build()?;
     Ok(window)
-}
\ No newline at end of file
+}
+
+#[derive(Debug,Clone,Copy)]
+/// Areas of the window.
+///
+/// # Fields
+/// - `all`: The whole window.
+/// - `render`: The 3D view, on top.
+/// - `minimap`: The square under the 3D view, on the left.
+/// - `interface`: What remains under the 3D view, on the right.
+pub struct Layout {
+    pub all: Rect,
+    pub render: Rect,
+    pub minimap: Rect,
+    pub interface: Rect,
+}
+
+impl Layout {
+    pub fn new(width: u32,height: u32,hud_height: u32) -> Self {
+        Self {
+            all: Rect::new(0, 0, width, height + hud_height),
+            render: Rect::new(0, 0, width, height),
+            minimap: Rect::new(0, height as i32 + 1 , hud_height, hud_height),
+            interface: Rect::new(hud_height as i32+ 1, height as i32 + 1, width - hud_height, hud_height),
+        }
+    }
+}
+
+/// Builds the map and loads its textures from a map configuration.
+pub fn load_map<'a>(loader: &Loader,texture_creator: &'a TextureCreator<WindowContext>) -> Result<(Map,TextureManager<'a,WindowContext>),Box<dyn Error>> {
+    let mut texture_manager = TextureManager::new(texture_creator);
+    let textures = loader.get_resources().textures()?;
+    let textures_ref: HashMap<&str, &str> = textures.iter()
+        .map(|(k, v)| (k.as_str(), v.as_str()))
+        .collect();
+    texture_manager.load_from_map(textures_ref)?;
+    Ok((Map::from(loader),texture_manager))
+}
+
+/// Loads the textures of `skins` next to those of the map, under the name of
+/// each skin. Those already loaded are kept, and a texture that can't be
+/// loaded only leaves its players undrawn.
+pub fn load_skins(textures: &mut TextureManager<WindowContext>,skins: &[Skin]) {
+    for skin in skins {
+        if let Err(e) = textures.load(skin.name.as_str(), skin.texture.as_str()) {
+            eprintln!("can't load the texture of skin \"{}\": {}", skin.name, e);
+        }
+    }
+}
+
+/// Loads the first font of the map configuration, by name, or `DEFAULT_FONT`.
+pub fn load_font<'t>(loader: &Loader,ttf: &'t Sdl2TtfContext) -> Result<Font<'t,'static>,Box<dyn Error>> {
+    let fonts = loader.get_resources().fonts()?;
+    let (path, size) = match fonts.iter().min_by(|a, b| a.0.cmp(b.0)) {
+        Some((_, (path, size))) => (path.as_str(), *size),
+        None => DEFAULT_FONT,
+    };
+    Ok(ttf.load_font(path, size)?)
+}
+
+/// Draws `value` with its top left corner at `at`, in the current viewport.
+pub fn text(canvas: &mut Canvas<Window>,font: &Font,texture_creator: &TextureCreator<WindowContext>,value: &str,at: (i32,i32),color: Color) -> Result<(),Box<dyn Error>> {
+    let surface = font.render(value).blended(color)?;
+    let texture = texture_creator.create_texture_from_surface(&surface)?;
+    canvas.copy(&texture, None, Rect::new(at.0, at.1, surface.width(), surface.height()))?;
+    Ok(())
+}
+
+/// The players to draw at the server time `time`, those under spawn
+/// protection until the time of `protections` blinking. Everyone is drawn if
+/// the clock isn't synchronized.
+pub fn blinking(players: &Players,protections: &HashMap<String, u64>,time: Option<u64>) -> Players {
+    let mut shown = Players::new();
+    for player in players.iter() {
+        let protected = |t: u64| protections.get(&player.nickname).is_some_and(|until| t < *until);
+        let hidden = time.is_some_and(|t| protected(t) && (t / BLINK) % 2 == 1);
+        if !hidden {
+            shown.push(player.clone());
+        }
+    }
+    shown
+}
+
+/// Sprites of the flags. A carried flag is drawn on its carrier, and not at
+/// all if the carrier is the local player or out of view.
+pub fn flag_sprites(flags: &[Flag],players: &Players) -> Vec<(FPoint,String)> {
+    let mut sprites = Vec::new();
+    for flag in flags {
+        let position = match flag.carrier() {
+            Some(nickname) => match players.get_by_nickname(&nickname) {
+                Some(index) => FPoint::new(players[index].x, players[index].y),
+                None => continue,
+            },
+            None => FPoint::new(flag.x, flag.y),
+        };
+        sprites.push((position, flag.texture()));
+    }
+    sprites
+}
+
+/// Sprites of the projectiles, where they are at the server time `time`.
+pub fn projectile_sprites(projectiles: &[Projectile],time: u64) -> Vec<(FPoint,String)> {
+    projectiles.iter()
+        .map(|p| {
+            let (x, y) = p.position_after(p.traveled_at(time));
+            (FPoint::new(x, y), p.texture.clone())
+        })
+        .collect()
+}
+
+/// Sprites of the items that can be picked up.
+pub fn item_sprites(items: &[Item]) -> Vec<(FPoint,String)> {
+    items.iter()
+        .filter(|i| i.available())
+        .map(|i| (FPoint::new(i.x, i.y), i.texture.clone()))
+        .collect()
+}
+
+/// Draws what `camera` sees, walls then `players` and the other `sprites`, in
+/// the render zone. The players and sprites whose texture isn't loaded are skipped.
+pub fn draw_view(canvas: &mut Canvas<Window>,layout: &Layout,camera: Camera,map: &Map,textures: &TextureManager<WindowContext>,players: &Players,sprites: &[(FPoint,String)]) -> Result<(),Box<dyn Error>> {
+    canvas.set_viewport(layout.render);
+    let mut rays = camera.cast_rays(map.clone(), layout.render.width());
+    rays.display(canvas, Some(textures))?;
+    let mut render_datas = vec![];
+    for other in players.iter().filter(|p| textures.get(p.texture.as_str()).is_some()) {
+        render_datas.push(other.into_render(camera, map,&rays));
+    }
+    for (position, texture) in sprites.iter().filter(|(_, t)| textures.get(t).is_some()) {
+        render_datas.push(RenderData::new(camera, map.clone(), *position, 0.0, texture.clone(), rays.clone()));
+    }
+    render_datas.sort();
+    for mut rd in render_datas {
+        rd.display(canvas, Some(textures))?;
+    }
+    Ok(())
+}
+
+/// Draws the minimap centered on the camera in the minimap zone, the flags
+/// lying on the ground being outlined with the color of their team.
+pub fn draw_minimap(canvas: &mut Canvas<Window>,layout: &Layout,camera: Camera,map: &Map,players: &Players,flags: &[Flag]) -> Result<(),Box<dyn Error>> {
+    canvas.set_viewport(layout.minimap);
+    let mut minimap = Minimap::new(map, &FPoint::new(camera.position.0, camera.position.1), Color::GRAY, Color::BLACK);
+    // Players are red, or drawn with the color of their team in the team modes
+    minimap.set_colored_others(players.iter().map(|p| (FPoint::new(p.x, p.y), p.team.map_or(Color::RED, |t| t.color()))).collect());
+    minimap.set_markers(flags.iter().filter(|f| f.carrier().is_none()).map(|f| (FPoint::new(f.x, f.y), f.team.color())).collect());
+    minimap.set_target_pinpoint(Some(Color::YELLOW));
+    minimap.display::<()>(canvas, None)?;
+    Ok(())
+}
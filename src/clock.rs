@@ -0,0 +1,164 @@
+use std::{collections::VecDeque, time::{Duration, Instant}};
+
+/// Measurements the estimate is computed from.
+const SAMPLES: usize = 8;
+/// Time between two pings once the estimate is ready.
+const PING_INTERVAL: Duration = Duration::from_secs(1);
+/// Time between two pings until `SAMPLES` answers arrived.
+const WARMUP_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Client side estimate of the round trip time and of the server clock.
+///
+/// Server time is the reference of every timestamp of the protocol: the
+/// milliseconds elapsed since the server started. The client stamps an
+/// `InputData::Ping` with its local time, the server answers with an
+/// `OutputData::Pong` carrying it back along with its own time, and
+///
+/// `offset = server + rtt / 2 - local now`
+///
+/// The offset of the exchange with the smallest round trip is kept, as its
+/// error is at most half of that round trip.
+///
+/// # Fields
+/// - `epoch`: Origin of the local time.
+/// - `samples`: The last round trips and offsets, in milliseconds.
+/// - `last_ping`: When the last ping was sent.
+pub struct ServerClock {
+    epoch: Instant,
+    samples: VecDeque<(u64, i64)>,
+    last_ping: Option<Instant>,
+}
+
+impl Default for ServerClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ServerClock {
+    pub fn new() -> Self {
+        Self { epoch: Instant::now(), samples: VecDeque::with_capacity(SAMPLES), last_ping: None }
+    }
+
+    /// Local time in milliseconds, as carried by `InputData::Ping`.
+    pub fn local_ms(&self) -> u64 {
+        self.epoch.elapsed().as_millis() as u64
+    }
+
+    /// Tells if a ping must be sent now and, if so, counts it as sent.
+    ///
+    /// Pings are sent quickly until the estimate is ready, then every second.
+    pub fn ping_due(&mut self) -> bool {
+        let interval = if self.samples.len() < SAMPLES { WARMUP_INTERVAL } else { PING_INTERVAL };
+        if self.last_ping.is_some_and(|t| t.elapsed() < interval) {
+            return false;
+        }
+        self.last_ping = Some(Instant::now());
+        true
+    }
+
+    /// Records the answer to a ping.
+    ///
+    /// # Arguments
+    /// * `local` - The local time the ping was sent at, echoed by the server.
+    /// * `server` - The server time the server answered at.
+    /// * `received` - When the answer arrived, so the time it then waited to be read isn't counted.
+    pub fn pong(&mut self, local: u64, server: u64, received: Instant) {
+        let now = received.saturating_duration_since(self.epoch).as_millis() as u64;
+        if local > now {
+            return;
+        }
+        let rtt = now - local;
+        let offset = server as i64 + (rtt / 2) as i64 - now as i64;
+        if self.samples.len() == SAMPLES {
+            self.samples.pop_front();
+        }
+        self.samples.push_back((rtt, offset));
+    }
+
+    /// Average round trip time of the last exchanges, `None` before the first one.
+    pub fn rtt(&self) -> Option<Duration> {
+        if self.samples.is_empty() {
+            return None;
+        }
+        let total: u64 = self.samples.iter().map(|(rtt, _)| rtt).sum();
+        Some(Duration::from_millis(total / self.samples.len() as u64))
+    }
+
+    /// Milliseconds to add to the local time to get the server time.
+    pub fn offset(&self) -> Option<i64> {
+        self.samples.iter().min_by_key(|(rtt, _)| *rtt).map(|(_, offset)| *offset)
+    }
+
+    /// Estimated current server time, `None` before the first exchange.
+    pub fn server_ms(&self) -> Option<u64> {
+        self.offset().map(|offset| (self.local_ms() as i64 + offset).max(0) as u64)
+    }
+
+    /// Time left until the server time `time`, zero if it is past.
+    pub fn until(&self, time: u64) -> Option<Duration> {
+        self.server_ms().map(|now| Duration::from_millis(time.saturating_sub(now)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Answers a ping sent at `local`, received at `received` local time.
+    fn exchange(clock: &mut ServerClock,local: u64,server: u64,received: u64) {
+        let received = clock.epoch + Duration::from_millis(received);
+        clock.pong(local, server, received);
+    }
+
+    #[test]
+    fn nothing_is_known_before_the_first_answer() {
+        let clock = ServerClock::new();
+        assert_eq!(clock.rtt(), None);
+        assert_eq!(clock.offset(), None);
+        assert_eq!(clock.until(1000), None);
+    }
+
+    #[test]
+    fn offset_accounts_for_half_the_round_trip() {
+        let mut clock = ServerClock::new();
+        exchange(&mut clock, 100, 5_000, 140);
+        assert_eq!(clock.rtt(), Some(Duration::from_millis(40)));
+        assert_eq!(clock.offset(), Some(5_000 + 20 - 140));
+    }
+
+    #[test]
+    fn the_shortest_round_trip_gives_the_offset() {
+        let mut clock = ServerClock::new();
+        exchange(&mut clock, 0, 1_000, 100);
+        exchange(&mut clock, 200, 1_150, 210);
+        exchange(&mut clock, 300, 1_300, 380);
+        assert_eq!(clock.offset(), Some(1_150 + 5 - 210));
+        assert_eq!(clock.rtt(), Some(Duration::from_millis((100 + 10 + 80) / 3)));
+    }
+
+    #[test]
+    fn only_the_last_samples_are_kept() {
+        let mut clock = ServerClock::new();
+        exchange(&mut clock, 0, 1_000, 2);
+        for i in 1..=SAMPLES as u64 {
+            exchange(&mut clock, i * 100, 2_000, i * 100 + 50);
+        }
+        assert_eq!(clock.samples.len(), SAMPLES);
+        assert_eq!(clock.rtt(), Some(Duration::from_millis(50)));
+    }
+
+    #[test]
+    fn answers_from_the_future_are_ignored() {
+        let mut clock = ServerClock::new();
+        exchange(&mut clock, 500, 1_000, 100);
+        assert_eq!(clock.offset(), None);
+    }
+
+    #[test]
+    fn pings_are_sent_once_per_interval() {
+        let mut clock = ServerClock::new();
+        assert!(clock.ping_due());
+        assert!(!clock.ping_due());
+    }
+}
@@ -1,7 +1,7 @@
 use std::net::SocketAddr;
 
 use serde::{Deserialize, Serialize};
-use super::update::default_addr;
+use super::{team::Team, update::default_addr};
 
 #[derive(Deserialize,Serialize,Debug)]
 pub struct Connection {
@@ -9,6 +9,18 @@ pub struct Connection {
     pub addr: SocketAddr,
 
     pub nickname: String,
+
+    /// Password asked by the server, if any
+    #[serde(default)]
+    pub password: Option<String>,
+
+    /// Team asked for in the team modes. The server may pick the other one to keep them balanced
+    #[serde(default)]
+    pub team: Option<Team>,
+
+    /// Skin asked for. The server gives its default one if it doesn't allow it
+    #[serde(default)]
+    pub skin: Option<String>,
 }
 
 impl Connection {
@@ -0,0 +1,31 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::data::Team;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", content = "data")]
+/// Something that happened in the match, told to every client for the kill feed.
+pub enum GameEvent {
+    /// `killer` killed `victim` with the weapon named `weapon`. Both are the same player on a suicide.
+    Kill { killer: String, victim: String, weapon: String },
+    /// The player of this nickname joined the server
+    Join(String),
+    /// The player of this nickname left the server, or has been removed from it
+    Leave(String),
+    /// `nickname` captured a flag, scoring for `team`
+    Capture { nickname: String, team: Team },
+}
+
+impl fmt::Display for GameEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GameEvent::Kill { killer, victim, weapon } if killer == victim => write!(f, "{} [{}] suicide", victim, weapon),
+            GameEvent::Kill { killer, victim, weapon } => write!(f, "{} [{}] {}", killer, weapon, victim),
+            GameEvent::Join(nickname) => write!(f, "{} joined", nickname),
+            GameEvent::Leave(nickname) => write!(f, "{} left", nickname),
+            GameEvent::Capture { nickname, team } => write!(f, "{} captured a flag for {}", nickname, team),
+        }
+    }
+}
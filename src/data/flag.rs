@@ -0,0 +1,45 @@
+use serde::{Deserialize, Serialize};
+
+use crate::data::Team;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type",content = "data")]
+/// Where a flag of the capture-the-flag mode is.
+pub enum FlagState {
+    /// On its base, waiting to be taken
+    AtBase,
+    /// Carried by the player of this nickname
+    Carried(String),
+    /// Lying where its carrier died, until it goes back to its base at the server time `until`
+    Dropped { until: u64 },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+/// A flag of the capture-the-flag mode, sent to every client with `OutputData::Flags`.
+///
+/// # Fields
+/// - `team`: Team defending it.
+/// - `x`, `y`: Its position. While it is carried, the position of its carrier
+///   on the server, and of its base for the clients, which draw it on the carrier.
+/// - `state`: Where it is.
+pub struct Flag {
+    pub team: Team,
+    pub x: f32,
+    pub y: f32,
+    pub state: FlagState,
+}
+
+impl Flag {
+    /// Name of the texture the flag is drawn with, which the map must load.
+    pub fn texture(&self) -> String {
+        format!("flag_{}", self.team)
+    }
+
+    /// Nickname of the player carrying the flag, if any.
+    pub fn carrier(&self) -> Option<&str> {
+        match &self.state {
+            FlagState::Carried(nickname) => Some(nickname),
+            _ => None,
+        }
+    }
+}
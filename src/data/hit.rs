@@ -0,0 +1,20 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+/// A shot that hit a player, sent to the shooter and to the target.
+///
+/// # Fields
+/// - `shooter`: Nickname of the player who fired.
+/// - `target`: Nickname of the player hit.
+/// - `damage`: Health and armor the hit took away.
+/// - `health`: Health of the target after the hit.
+/// - `armor`: Armor of the target after the hit.
+/// - `fatal`: Whether the hit killed the target.
+pub struct Hit {
+    pub shooter: String,
+    pub target: String,
+    pub damage: u32,
+    pub health: u32,
+    pub armor: u32,
+    pub fatal: bool,
+}
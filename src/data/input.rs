@@ -1,6 +1,6 @@
-use std::{net::UdpSocket, net::SocketAddr};
+use std::net::SocketAddr;
 
-use crate::data::{default_addr, Connection, Update};
+use crate::{data::{default_addr, Connection, Update}, transport::{Transport, MAX_DATAGRAM}};
 use serde::{Deserialize, Serialize};
 
 #[derive(Deserialize,Serialize, Debug)]
@@ -13,14 +13,75 @@ pub enum InputData {
         addr: SocketAddr,
     },
     Shoot(Update),
+    /// Asks the server to respawn a dead player, once its delay is over
+    Respawn {
+        #[serde(skip,default = "default_addr")]
+        addr: SocketAddr,
+    },
+    /// Puts in hand the weapon of this index in the weapons of the server
+    Switch {
+        #[serde(skip,default = "default_addr")]
+        addr: SocketAddr,
+        weapon: usize,
+    },
+    /// Refills the magazine of the weapon in hand
+    Reload {
+        #[serde(skip,default = "default_addr")]
+        addr: SocketAddr,
+    },
+    /// Round trip and clock measurement started by a client, carrying its own time
+    Ping {
+        #[serde(skip,default = "default_addr")]
+        addr: SocketAddr,
+        time: u64,
+    },
+    /// Answer to `OutputData::Ping`, echoing its server time
+    Pong {
+        #[serde(skip,default = "default_addr")]
+        addr: SocketAddr,
+        time: u64,
+    },
     Unknown, // Malformed request
     None, // nothing recieved
 }
 
 impl InputData {
-    pub fn parse(socket: &UdpSocket) -> Result<Self, Box<std::io::Error>> {
-        // init a buffer of 1024 bytes
-        let mut buf = [0; 1024];
+    /// Address of the client that sent the message, if it carries one.
+    pub fn addr(&self) -> Option<SocketAddr> {
+        match self {
+            InputData::Connection(value) => Some(value.addr),
+            InputData::Update(value) | InputData::Shoot(value) => Some(value.addr),
+            InputData::Disconnection { addr } | InputData::Respawn { addr } | InputData::Switch { addr, .. } | InputData::Reload { addr } | InputData::Ping { addr, .. } | InputData::Pong { addr, .. } => Some(*addr),
+            InputData::Unknown | InputData::None => None,
+        }
+    }
+
+    /// Name of the message type, as written in the `type` field of the JSON.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            InputData::Connection(_) => "Connection",
+            InputData::Update(_) => "Update",
+            InputData::Disconnection { .. } => "Disconnection",
+            InputData::Shoot(_) => "Shoot",
+            InputData::Respawn { .. } => "Respawn",
+            InputData::Switch { .. } => "Switch",
+            InputData::Reload { .. } => "Reload",
+            InputData::Ping { .. } => "Ping",
+            InputData::Pong { .. } => "Pong",
+            InputData::Unknown => "Unknown",
+            InputData::None => "None",
+        }
+    }
+
+    pub fn parse(socket: &dyn Transport) -> Result<Self, Box<std::io::Error>> {
+        Ok(Self::receive(socket)?.0)
+    }
+
+    /// Same as `parse`, also returning the size in bytes of the packet.
+    ///
+    /// A packet that isn't valid UTF-8 JSON is returned as `Unknown`.
+    pub fn receive(socket: &dyn Transport) -> Result<(Self, usize), Box<std::io::Error>> {
+        let mut buf = [0; MAX_DATAGRAM];
 
         // data reception (non-blocking)
         let opts = match socket.recv_from(&mut buf) {
@@ -36,19 +97,21 @@ impl InputData {
         // 
         let (size, socket_addr) = match opts {
             Some(values) => values,
-            None => return Ok(InputData::None),
+            None => return Ok((InputData::None, 0)),
         };
 
-        let data = String::from_utf8(buf[..size].to_vec())
-            .map_err(|e| Box::new(std::io::Error::new(std::io::ErrorKind::InvalidData, e)))?;
+        let data = match String::from_utf8(buf[..size].to_vec()) {
+            Ok(d) => d,
+            Err(_) => return Ok((InputData::Unknown, size)),
+        };
 
         let mut msg = serde_json::from_str::<InputData>(&data).unwrap_or(InputData::Unknown);
         match &mut msg {
-            InputData::Update(value) => value.addr = socket_addr,
+            InputData::Update(value) | InputData::Shoot(value) => value.addr = socket_addr,
             InputData::Connection(value) => value.addr = socket_addr,
-            InputData::Disconnection { addr } => *addr = socket_addr,
+            InputData::Disconnection { addr } | InputData::Respawn { addr } | InputData::Switch { addr, .. } | InputData::Reload { addr } | InputData::Ping { addr, .. } | InputData::Pong { addr, .. } => *addr = socket_addr,
             _ => {},
         }
-        Ok(msg)
+        Ok((msg, size))
     }
 }
\ No newline at end of file
@@ -0,0 +1,38 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type",content = "data")]
+/// What picking up an item gives.
+pub enum ItemKind {
+    /// Health, up to the maximum
+    Health(u32),
+    /// Armor, up to the maximum
+    Armor(u32),
+    /// Full magazines for every weapon held, ending any reload
+    Ammo,
+    /// The weapon of this name, with a full magazine
+    Weapon(String),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+/// An item placed by the map, sent to every client with `OutputData::Items`.
+///
+/// # Fields
+/// - `kind`: What it gives.
+/// - `x`, `y`: Its position.
+/// - `texture`: Name of the texture it is drawn with.
+/// - `back_at`: Server time it comes back at once taken, `None` while it can be picked up.
+pub struct Item {
+    pub kind: ItemKind,
+    pub x: f32,
+    pub y: f32,
+    pub texture: String,
+    pub back_at: Option<u64>,
+}
+
+impl Item {
+    /// Whether the item can be picked up.
+    pub fn available(&self) -> bool {
+        self.back_at.is_none()
+    }
+}
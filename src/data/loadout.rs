@@ -0,0 +1,46 @@
+use serde::{Deserialize, Serialize};
+
+use crate::entities::Weapon;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+/// Weapons of a player, kept by the server and sent to its client with
+/// `OutputData::Loadout` when it changes.
+///
+/// # Fields
+/// - `weapon`: Index, in the weapons of the server, of the one in hand.
+/// - `ammo`: Shots left in the magazine of each weapon, `None` for the
+///   weapons the player doesn't hold.
+/// - `ready_at`: Server time from which the player can fire again, after a shot or a reload.
+pub struct Loadout {
+    pub weapon: usize,
+    pub ammo: Vec<Option<u32>>,
+    pub ready_at: u64,
+}
+
+impl Loadout {
+    /// The starting weapons with full magazines, the first one in hand.
+    pub fn new(weapons: &[Weapon]) -> Self {
+        Self {
+            weapon: weapons.iter().position(|w| w.starting).unwrap_or(0),
+            ammo: weapons.iter().map(|w| w.starting.then_some(w.ammo)).collect(),
+            ready_at: 0,
+        }
+    }
+
+    /// Shots left in the weapon in hand.
+    pub fn ammo(&self) -> u32 {
+        self.ammo.get(self.weapon).copied().flatten().unwrap_or(0)
+    }
+
+    /// Whether the player holds the weapon of index `weapon`.
+    pub fn owns(&self, weapon: usize) -> bool {
+        self.ammo.get(weapon).is_some_and(|a| a.is_some())
+    }
+
+    /// Holds the weapon of index `weapon`, with `ammo` shots left in its magazine.
+    pub fn give(&mut self, weapon: usize, ammo: u32) {
+        if let Some(slot) = self.ammo.get_mut(weapon) {
+            *slot = Some(ammo);
+        }
+    }
+}
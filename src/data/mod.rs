@@ -18,5 +18,32 @@ pub use update::default_addr;
 mod input;
 pub use input::InputData;
 
+mod hit;
+pub use hit::Hit;
+
+mod flag;
+pub use flag::{Flag, FlagState};
+
+mod item;
+pub use item::{Item, ItemKind};
+
+mod loadout;
+pub use loadout::Loadout;
+
+mod projectile;
+pub use projectile::Projectile;
+
+mod phase;
+pub use phase::Phase;
+
+mod score;
+pub use score::{Score, Standings, Stats};
+
+mod team;
+pub use team::Team;
+
+mod event;
+pub use event::GameEvent;
+
 mod output;
 pub use output::OutputData;
\ No newline at end of file
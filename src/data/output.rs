@@ -1,23 +1,96 @@
-use std::net::UdpSocket;
-
-use crate::{data::{Deny, Update}, entities::{Player, Players}, Loader};
+use crate::{data::{Deny, Flag, GameEvent, Hit, Item, Loadout, Phase, Projectile, Standings, Update}, entities::{Player, Players, Skin, Weapon}, transport::{Transport, MAX_DATAGRAM}, Loader};
 pub use serde::{Deserialize,Serialize};
+use std::time::Instant;
 
-#[derive(Deserialize,Serialize, Debug)]
+#[derive(Deserialize,Serialize, Debug, Clone)]
 #[serde(tag = "type",content = "data")] // <= ajoute un champ "type" dans le JSON
 pub enum OutputData {
     Update(Update),
     AccessDeny(Deny),
     Connecting((Player,Players,Loader)),
     New(Player),
+    /// A shot of the client hit someone, or the client has been hit
+    Hit(Hit),
+    /// The player of this nickname left the view of the client and must be removed
+    Hide(String),
+    /// The server switched maps: the respawned player, the others and the new map
+    MapChange((Player,Players,Loader)),
+    /// State of every flag of the capture-the-flag mode, sent when one changes
+    Flags(Vec<Flag>),
+    /// Every item of the map, sent on connection and on map change
+    Items(Vec<Item>),
+    /// The item of this index has been taken, until `back_at`, or is back if `None`
+    ItemState { index: usize, back_at: Option<u64> },
+    /// Health and armor of the client, sent when they change other than by a hit
+    Vitals { health: u32, armor: u32 },
+    /// Definitions of the weapons the players can hold, sent on connection
+    Weapons(Vec<Weapon>),
+    /// Skins the players can wear, sent on connection for the client to load their textures
+    Skins(Vec<Skin>),
+    /// Weapons of the client, sent when the server changes them or refuses a shot
+    Loadout(Loadout),
+    /// The player of this nickname can't be hurt until this server time, 0 once it fired
+    Protection { nickname: String, until: u64 },
+    /// A projectile has been fired
+    Projectile(Projectile),
+    /// The projectile of this id exploded at this position
+    Explosion { id: u32, x: f32, y: f32 },
+    /// The match moved to a new phase
+    Phase(Phase),
+    /// Standing of every player and team
+    Scores(Standings),
+    /// Something happened in the match, sent to every client
+    Event(GameEvent),
+    /// Text sent by the server administrator
+    Message(String),
+    /// Round trip measurement, carrying the server time
+    Ping(u64),
+    /// Answer to `InputData::Ping`, echoing the client time along with the server time.
+    /// `received` is set by the client when the datagram arrives, never sent.
+    Pong {
+        client: u64,
+        server: u64,
+        #[serde(skip)]
+        received: Option<Instant>,
+    },
     Unknown,
     None,
 }
 
 impl OutputData {
-    pub fn parse(socket: &UdpSocket) -> Result<Self, Box<std::io::Error>> {
-        // init a buffer of 4096 bytes
-        let mut buf = [0; 4096];
+    /// Name of the message type, as written in the `type` field of the JSON.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Update(_) => "Update",
+            Self::AccessDeny(_) => "AccessDeny",
+            Self::Connecting(_) => "Connecting",
+            Self::New(_) => "New",
+            Self::Hide(_) => "Hide",
+            Self::Hit(_) => "Hit",
+            Self::MapChange(_) => "MapChange",
+            Self::Flags(_) => "Flags",
+            Self::Items(_) => "Items",
+            Self::ItemState { .. } => "ItemState",
+            Self::Vitals { .. } => "Vitals",
+            Self::Weapons(_) => "Weapons",
+            Self::Skins(_) => "Skins",
+            Self::Loadout(_) => "Loadout",
+            Self::Protection { .. } => "Protection",
+            Self::Projectile(_) => "Projectile",
+            Self::Explosion { .. } => "Explosion",
+            Self::Phase(_) => "Phase",
+            Self::Scores(_) => "Scores",
+            Self::Event(_) => "Event",
+            Self::Message(_) => "Message",
+            Self::Ping(_) => "Ping",
+            Self::Pong { .. } => "Pong",
+            Self::Unknown => "Unknown",
+            Self::None => "None",
+        }
+    }
+
+    pub fn parse(socket: &dyn Transport) -> Result<Self, Box<std::io::Error>> {
+        let mut buf = [0; MAX_DATAGRAM];
 
         // data reception (non-blocking)
         let opts = match socket.recv_from(&mut buf) {
@@ -0,0 +1,31 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type",content = "data")]
+/// Step of the match lifecycle, sent to every client when it changes.
+///
+/// Times are in server time, like every timestamp of the protocol.
+pub enum Phase {
+    /// Kills don't count yet. The match starts at `until`, or waits for
+    /// more players if unset
+    Warmup { until: Option<u64> },
+    /// Kills count. The match ends at `until` if there is a time limit, or
+    /// once a player reaches `frag_limit` if there is one
+    InProgress { until: Option<u64>, frag_limit: Option<u32> },
+    /// The match is over and the next map starts at `until`. `winner` is
+    /// unset on a draw
+    Intermission { winner: Option<String>, until: u64 },
+}
+
+impl Default for Phase {
+    fn default() -> Self {
+        Self::Warmup { until: None }
+    }
+}
+
+impl Phase {
+    /// Whether players can shoot, which they can't during the intermission.
+    pub fn playing(&self) -> bool {
+        !matches!(self, Self::Intermission { .. })
+    }
+}
@@ -0,0 +1,39 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+/// A projectile in flight, sent to every client with `OutputData::Projectile`
+/// when fired. The clients move it along its straight flight themselves until
+/// `OutputData::Explosion` tells them where it ended.
+///
+/// # Fields
+/// - `id`: Identifier of the projectile, unique on the server.
+/// - `owner`: Nickname of the player who fired it.
+/// - `x`, `y`: Where it was fired from.
+/// - `d`: Direction of its flight.
+/// - `speed`: Tiles travelled every second.
+/// - `fired_at`: Server time it was fired at.
+/// - `until`: Server time it reaches the end of its range at.
+/// - `texture`: Name of the texture it is drawn with.
+pub struct Projectile {
+    pub id: u32,
+    pub owner: String,
+    pub x: f32,
+    pub y: f32,
+    pub d: f32,
+    pub speed: f32,
+    pub fired_at: u64,
+    pub until: u64,
+    pub texture: String,
+}
+
+impl Projectile {
+    /// Tiles travelled at the server time `time`.
+    pub fn traveled_at(&self, time: u64) -> f32 {
+        self.speed * time.saturating_sub(self.fired_at) as f32 / 1000.0
+    }
+
+    /// Position once `distance` tiles have been travelled.
+    pub fn position_after(&self, distance: f32) -> (f32,f32) {
+        (self.x + self.d.cos() * distance, self.y + self.d.sin() * distance)
+    }
+}
@@ -0,0 +1,143 @@
+use std::cmp::Ordering;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{data::Team, entities::Player};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+/// Kills and deaths of a player since it joined, kept by the server.
+///
+/// # Fields
+/// - `kills`: Players it killed.
+/// - `deaths`: Times it died, suicides included.
+/// - `suicides`: Times it killed itself.
+/// - `streak`: Kills since its last death.
+/// - `best_streak`: Longest streak it reached.
+/// - `captures`: Flags it brought back to its base, in capture-the-flag.
+pub struct Stats {
+    pub kills: u32,
+    pub deaths: u32,
+    pub suicides: u32,
+    pub streak: u32,
+    pub best_streak: u32,
+    #[serde(default)]
+    pub captures: u32,
+}
+
+impl Stats {
+    pub fn kill(&mut self) {
+        self.kills += 1;
+        self.streak += 1;
+        self.best_streak = self.best_streak.max(self.streak);
+    }
+
+    pub fn death(&mut self) {
+        self.deaths += 1;
+        self.streak = 0;
+    }
+
+    /// A suicide counts as a death and costs a point.
+    pub fn suicide(&mut self) {
+        self.suicides += 1;
+        self.death();
+    }
+
+    /// Kills minus suicides.
+    pub fn score(&self) -> i32 {
+        self.kills as i32 - self.suicides as i32
+    }
+
+    /// Kills per death, the kills themselves while the player never died.
+    pub fn ratio(&self) -> f32 {
+        self.kills as f32 / self.deaths.max(1) as f32
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+/// A line of the scoreboard.
+///
+/// # Fields
+/// - `nickname`: The player it is about.
+/// - `team`: Its team, in the team modes.
+/// - `stats`: Its kills and deaths.
+/// - `ping`: Its last round trip time measured by the server, in milliseconds.
+/// - `bot`: Whether it is driven by the server.
+pub struct Score {
+    pub nickname: String,
+    #[serde(default)]
+    pub team: Option<Team>,
+    pub stats: Stats,
+    pub ping: u32,
+    pub bot: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+/// The scoreboard, sent to every client with `OutputData::Scores`.
+///
+/// # Fields
+/// - `players`: Every player, best first.
+/// - `teams`: Score of each team, best first. Empty outside of the team modes.
+pub struct Standings {
+    pub players: Vec<Score>,
+    #[serde(default)]
+    pub teams: Vec<(Team, i32)>,
+}
+
+impl Score {
+    pub fn new(player: &Player) -> Self {
+        Self { nickname: player.nickname.clone(), team: player.team, stats: player.stats, ping: player.ping, bot: player.bot }
+    }
+
+    /// Sorts `scores` best first: by score, then by fewest deaths, then by nickname.
+    pub fn sort(scores: &mut [Score]) {
+        scores.sort_by(|a, b| match b.stats.score().cmp(&a.stats.score()) {
+            Ordering::Equal => a.stats.deaths.cmp(&b.stats.deaths).then_with(|| a.nickname.cmp(&b.nickname)),
+            order => order,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn score(nickname: &str,kills: u32,deaths: u32,suicides: u32) -> Score {
+        let stats = Stats { kills, deaths, suicides, ..Stats::default() };
+        Score { nickname: nickname.to_string(), team: None, stats, ping: 0, bot: false }
+    }
+
+    #[test]
+    fn a_death_ends_the_streak() {
+        let mut stats = Stats::default();
+        stats.kill();
+        stats.kill();
+        stats.kill();
+        stats.death();
+        stats.kill();
+        assert_eq!((stats.kills, stats.deaths, stats.streak, stats.best_streak), (4, 1, 1, 3));
+    }
+
+    #[test]
+    fn a_suicide_ends_the_streak_and_costs_a_point() {
+        let mut stats = Stats::default();
+        stats.kill();
+        stats.kill();
+        stats.suicide();
+        assert_eq!((stats.deaths, stats.suicides, stats.streak, stats.best_streak), (1, 1, 0, 2));
+        assert_eq!(stats.score(), 1);
+    }
+
+    #[test]
+    fn ratio_counts_kills_while_never_dead() {
+        assert_eq!(score("a", 3, 0, 0).stats.ratio(), 3.0);
+        assert_eq!(score("a", 3, 2, 0).stats.ratio(), 1.5);
+    }
+
+    #[test]
+    fn scores_are_sorted_by_score_then_deaths_then_nickname() {
+        let mut scores = vec![score("dave", 2, 1, 1), score("carol", 1, 3, 0), score("bob", 1, 1, 0), score("alice", 1, 1, 0), score("eve", 5, 9, 0)];
+        Score::sort(&mut scores);
+        let order: Vec<_> = scores.iter().map(|s| s.nickname.as_str()).collect();
+        assert_eq!(order, ["eve", "alice", "bob", "dave", "carol"]);
+    }
+}
@@ -0,0 +1,33 @@
+use std::fmt;
+
+use sdl2::pixels::Color;
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+/// Side of a player in the team modes.
+pub enum Team {
+    Red,
+    Blue,
+}
+
+impl Team {
+    pub const ALL: [Team; 2] = [Team::Red, Team::Blue];
+
+    /// Color the team is drawn with on the minimap and the scoreboard.
+    pub fn color(self) -> Color {
+        match self {
+            Team::Red => Color::RGB(230, 60, 60),
+            Team::Blue => Color::RGB(70, 120, 240),
+        }
+    }
+}
+
+impl fmt::Display for Team {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Team::Red => write!(f, "red"),
+            Team::Blue => write!(f, "blue"),
+        }
+    }
+}
@@ -2,11 +2,17 @@ use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
 use serde::{Deserialize, Serialize};
 
 
-#[derive(Deserialize,Serialize,Debug,Clone,Copy)]
+#[derive(Deserialize,Serialize,Debug,Clone,Copy,PartialEq)]
+/// State of a player.
+///
+/// Like every timestamp of the protocol, the one of `Dead` is in server
+/// time: milliseconds elapsed since the server started. Clients convert it
+/// with `clock::ServerClock`.
 pub enum Status {
     Connecting,
     Alive,
     Disconnecting,
+    /// Server time from which the player can respawn
     Dead(u64),
 }
 
@@ -0,0 +1,159 @@
+use std::{fs::File, io::{BufRead, BufReader, BufWriter, Write}};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{data::{default_addr, OutputData, Status, Update}, entities::Players, Loader};
+
+/// Version written in every demo, bumped when the format changes.
+pub const DEMO_VERSION: u32 = 1;
+
+/// Milliseconds between two snapshots, so seeking never replays the whole file.
+const SNAPSHOT_INTERVAL: u64 = 10_000;
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(tag = "type", content = "data")]
+/// What happened at a given time of a recorded match.
+pub enum DemoEvent {
+    /// The whole state of the match. Always the first event of a demo.
+    Snapshot { loader: Loader, players: Players },
+    /// A message as it was sent to the clients.
+    Output(OutputData),
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+/// An event and the time it happened, in milliseconds since the recording started.
+pub struct DemoFrame {
+    pub time: u64,
+    pub event: DemoEvent,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+struct DemoHeader {
+    version: u32,
+}
+
+/// Writes a match to a demo file as it is played.
+///
+/// The file holds one JSON object per line: a header, then `DemoFrame`s in
+/// chronological order. Player movements are found by comparing the players
+/// with their state at the previous capture.
+pub struct DemoWriter {
+    file: BufWriter<File>,
+    previous: Players,
+    last_snapshot: u64,
+}
+
+impl DemoWriter {
+    /// Creates the demo file and writes its first snapshot.
+    pub fn create(path: &str, time: u64, loader: &Loader, players: &Players) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut file = BufWriter::new(File::create(path)?);
+        serde_json::to_writer(&mut file, &DemoHeader { version: DEMO_VERSION })?;
+        file.write_all(b"\n")?;
+        let mut writer = Self { file, previous: players.clone(), last_snapshot: time };
+        writer.snapshot(time, loader, players)?;
+        Ok(writer)
+    }
+
+    /// Writes the whole state of the match, e.g. after a map change.
+    pub fn snapshot(&mut self, time: u64, loader: &Loader, players: &Players) -> Result<(), Box<dyn std::error::Error>> {
+        self.write(DemoFrame { time, event: DemoEvent::Snapshot { loader: loader.clone(), players: players.clone() } })?;
+        self.previous = players.clone();
+        self.last_snapshot = time;
+        Ok(())
+    }
+
+    /// Writes a message that isn't deduced from the players' state.
+    pub fn record(&mut self, time: u64, data: &OutputData) -> Result<(), Box<dyn std::error::Error>> {
+        self.write(DemoFrame { time, event: DemoEvent::Output(data.clone()) })
+    }
+
+    /// Writes the players that joined, left or changed since the last capture.
+    pub fn capture(&mut self, time: u64, loader: &Loader, players: &Players) -> Result<(), Box<dyn std::error::Error>> {
+        if time - self.last_snapshot >= SNAPSHOT_INTERVAL {
+            self.snapshot(time, loader, players)?;
+            return self.file.flush().map_err(|e| e.into());
+        }
+        for player in players.iter() {
+            let event = match self.previous.iter().find(|p| p.nickname == player.nickname) {
+                None => OutputData::New(player.clone()),
+                Some(p) if (p.x, p.y, p.d, p.status) != (player.x, player.y, player.d, player.status) => {
+                    OutputData::Update(Update { addr: default_addr(), nickname: player.nickname.clone(), x: Some(player.x), y: Some(player.y), d: Some(player.d), status: Some(player.status) })
+                },
+                Some(_) => continue,
+            };
+            self.write(DemoFrame { time, event: DemoEvent::Output(event) })?;
+        }
+        for player in self.previous.clone().iter() {
+            if players.get_by_nickname(&player.nickname).is_none() {
+                let event = OutputData::Update(Update { addr: default_addr(), nickname: player.nickname.clone(), x: None, y: None, d: None, status: Some(Status::Disconnecting) });
+                self.write(DemoFrame { time, event: DemoEvent::Output(event) })?;
+            }
+        }
+        self.previous = players.clone();
+        Ok(())
+    }
+
+    fn write(&mut self, frame: DemoFrame) -> Result<(), Box<dyn std::error::Error>> {
+        serde_json::to_writer(&mut self.file, &frame)?;
+        self.file.write_all(b"\n")?;
+        Ok(())
+    }
+}
+
+impl Drop for DemoWriter {
+    fn drop(&mut self) {
+        let _ = self.file.flush();
+    }
+}
+
+/// A demo file loaded in memory.
+pub struct Demo {
+    pub frames: Vec<DemoFrame>,
+}
+
+impl Demo {
+    /// Reads a demo file.
+    ///
+    /// # Returns
+    /// - `Err` if the file can't be read, has another version or doesn't start with a snapshot.
+    pub fn from_file(path: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut lines = BufReader::new(File::open(path)?).lines();
+        let header: DemoHeader = match lines.next() {
+            Some(line) => serde_json::from_str(&line?)?,
+            None => return Err(format!("demo \"{}\" is empty", path).into()),
+        };
+        if header.version != DEMO_VERSION {
+            return Err(format!("demo \"{}\" has version {}, expected {}", path, header.version, DEMO_VERSION).into());
+        }
+        let mut frames = Vec::new();
+        for (n, line) in lines.enumerate() {
+            let line = line?;
+            match serde_json::from_str::<DemoFrame>(&line) {
+                Ok(frame) => frames.push(frame),
+                // The last line may be cut if the server was stopped while writing it
+                Err(e) if e.is_eof() => break,
+                Err(e) => return Err(format!("demo \"{}\" line {}: {}", path, n + 2, e).into()),
+            }
+        }
+        if !matches!(frames.first(), Some(DemoFrame { event: DemoEvent::Snapshot { .. }, .. })) {
+            return Err(format!("demo \"{}\" doesn't start with a snapshot", path).into());
+        }
+        Ok(Self { frames })
+    }
+
+    /// Time of the last event, in milliseconds.
+    pub fn duration(&self) -> u64 {
+        self.frames.last().map_or(0, |f| f.time)
+    }
+
+    /// Index of the last snapshot at or before `time`.
+    pub fn snapshot_before(&self, time: u64) -> usize {
+        self.frames.iter()
+            .enumerate()
+            .take_while(|(_, f)| f.time <= time)
+            .filter(|(_, f)| matches!(f.event, DemoEvent::Snapshot { .. }))
+            .map(|(i, _)| i)
+            .last()
+            .unwrap_or(0)
+    }
+}
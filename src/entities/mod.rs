@@ -3,9 +3,13 @@ mod render_data;
 // mod unliving;
 // mod pather;
 mod player;
+mod weapon;
+mod skin;
 
 pub use entity::{Entity,Context, PlacementData,Movable};
 pub use render_data::RenderData;
 // pub use unliving::NotMoving;
 // pub use pather::Pather;
 pub use player::*;
+pub use weapon::{Ballistics, Weapon};
+pub use skin::Skin;
@@ -1,9 +1,22 @@
 use std::{net::SocketAddr, ops::Deref, str::FromStr};
 
-use crate::{data::{default_addr, Status, Update}, entities::{entity::Movable, Entity}, world::Map};
+use crate::{data::{default_addr, Loadout, Stats, Status, Team, Update}, entities::{entity::Movable, Entity}, world::Map};
 use sdl2::rect::FPoint;
 use serde::{Deserialize,Serialize};
 
+/// Health of a player after spawning.
+pub const MAX_HEALTH: u32 = 100;
+/// Most armor a player can carry.
+pub const MAX_ARMOR: u32 = 100;
+/// Radius of a player's hitbox, in tiles.
+pub const HIT_RADIUS: f32 = 0.5;
+/// Share of the damage taken by the armor while it lasts.
+const ARMOR_ABSORPTION: f32 = 2.0 / 3.0;
+
+fn default_health() -> u32 {
+    MAX_HEALTH
+}
+
 #[derive(Debug, Clone,Serialize,Deserialize)]
 pub struct Player {
     // IP address and port of the Player.
@@ -28,11 +41,46 @@ pub struct Player {
     // pub texture: Rc<Texture>
 
     pub texture: String,
+
+    /// Last round trip time measured by the server, in milliseconds.
+    #[serde(default)]
+    pub ping: u32,
+
+    /// Whether the player is driven by the server instead of a client.
+    #[serde(default)]
+    pub bot: bool,
+
+    /// Health left, the player dies at 0.
+    #[serde(default = "default_health")]
+    pub health: u32,
+
+    /// Armor left, absorbing part of the damage.
+    #[serde(default)]
+    pub armor: u32,
+
+    /// Kills and deaths since the player joined.
+    #[serde(default)]
+    pub stats: Stats,
+
+    /// Side of the player in the team modes.
+    #[serde(default)]
+    pub team: Option<Team>,
+
+    /// Weapons held, only known by the server and the player's own client.
+    #[serde(skip)]
+    pub loadout: Loadout,
 }
 
 impl Player {
     pub fn new<D: AsRef<str>>(name: String,xyd: (f32,f32,f32),texture: D) -> Self {
-        Self { addr:default_addr(), nickname: name, x: xyd.0, y: xyd.1, d: xyd.2, status: Status::Alive, texture: texture.as_ref().to_string() }
+        Self { addr:default_addr(), nickname: name, x: xyd.0, y: xyd.1, d: xyd.2, status: Status::Alive, texture: texture.as_ref().to_string(), ping: 0, bot: false, health: MAX_HEALTH, armor: 0, stats: Stats::default(), team: None, loadout: Loadout::default() }
+    }
+
+    /// The player as the other clients get it: its health, armor, stats and
+    /// loadout, only known by the server and the player's own client, are left
+    /// to their defaults.
+    pub fn public_view(&self) -> Player {
+        Self { health: MAX_HEALTH, armor: 0, stats: Stats::default(), loadout: Loadout::default(), ..self.clone() }
     }
 
     pub fn update(&mut self, data: &Update) -> u8 {
@@ -58,11 +106,26 @@ impl Player {
         modif_datas
     }
 
-    pub fn shoot(&self, map: &Map, players: &Players, hit_radius: f32) -> Option<Player> {
+    /// Applies `damage`, part of it being absorbed by the armor.
+    ///
+    /// # Returns
+    /// * `true` if the player died.
+    pub fn take_damage(&mut self, damage: u32) -> bool {
+        let absorbed = ((damage as f32 * ARMOR_ABSORPTION).round() as u32).min(self.armor);
+        self.armor -= absorbed;
+        self.health = self.health.saturating_sub(damage - absorbed);
+        self.health == 0
+    }
+
+    /// Casts a shot from the player toward `d`, stopped by the walls.
+    ///
+    /// # Returns
+    /// * The first living player closer than `hit_radius` to the ray, within `range` tiles.
+    pub fn shoot(&self, map: &Map, players: &Players, hit_radius: f32, d: f32, range: f32) -> Option<Player> {
         let (mut x, mut y) = self.position();
         let step = 0.1; // précision du rayon
-        let dx = self.d.cos() * step;
-        let dy = self.d.sin() * step;
+        let dx = d.cos() * step;
+        let dy = d.sin() * step;
 
         loop {
             // Avancer le rayon
@@ -76,8 +139,8 @@ impl Player {
 
             // Vérifier joueurs
             for player in players.iter() {
-                if player.nickname == self.nickname {
-                    continue; // on ignore le tireur
+                if player.nickname == self.nickname || player.status != Status::Alive {
+                    continue; // on ignore le tireur et les morts
                 }
 
                 let px = player.x;
@@ -89,8 +152,8 @@ impl Player {
                 }
             }
 
-            // On fixe une distance max au rayon (évite boucle infinie si map vide)
-            if ((x - self.x).powi(2) + (y - self.y).powi(2)).sqrt() > 1000.0 {
+            // Portée de l'arme
+            if ((x - self.x).powi(2) + (y - self.y).powi(2)).sqrt() > range {
                 return None;
             }
         }
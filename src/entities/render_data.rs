@@ -94,59 +94,7 @@ impl RenderData {
 
     /// Uses DDA raycasting to check if a wall is between the Camera and the entity.
     fn is_behind_a_wall(&self, map: Map) -> bool {
-        let (start_x, start_y) = self.camera.position;
-        let (end_x, end_y) = (self.position.0, self.position.1);
-
-        let dir_x = end_x - start_x;
-        let dir_y = end_y - start_y;
-
-        let ray_len = (dir_x * dir_x + dir_y * dir_y).sqrt();
-        if ray_len == 0.0 {
-            return false; // Same position
-        }
-
-        let raydir_x = dir_x / ray_len;
-        let raydir_y = dir_y / ray_len;
-
-        let mut map_x = start_x.floor() as i32;
-        let mut map_y = start_y.floor() as i32;
-
-        let delta_dist_x = if raydir_x != 0.0 { (1.0 / raydir_x).abs() } else { f32::INFINITY };
-        let delta_dist_y = if raydir_y != 0.0 { (1.0 / raydir_y).abs() } else { f32::INFINITY };
-
-        let (step_x, mut side_dist_x) = if raydir_x < 0.0 {
-            (-1, (start_x - map_x as f32) * delta_dist_x)
-        } else {
-            (1, (map_x as f32 + 1.0 - start_x) * delta_dist_x)
-        };
-
-        let (step_y, mut side_dist_y) = if raydir_y < 0.0 {
-            (-1, (start_y - map_y as f32) * delta_dist_y)
-        } else {
-            (1, (map_y as f32 + 1.0 - start_y) * delta_dist_y)
-        };
-
-        let max_dist = ray_len;
-        let mut dist_traveled = 0.0;
-
-        // DDA loop
-        while dist_traveled < max_dist {
-            if side_dist_x < side_dist_y {
-                map_x += step_x;
-                dist_traveled = side_dist_x;
-                side_dist_x += delta_dist_x;
-            } else {
-                map_y += step_y;
-                dist_traveled = side_dist_y;
-                side_dist_y += delta_dist_y;
-            }
-
-            if let Some(true) = map.is_wall(map_x, map_y) {
-                return true; // Wall detected
-            }
-        }
-
-        false // No wall detected
+        !map.line_of_sight(self.camera.position, self.position)
     }
 }
 
@@ -0,0 +1,36 @@
+use std::{error::Error, fs};
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+/// Look of a player, read from the skins file of the server.
+///
+/// # Fields
+/// - `name`: Identifier of the skin, asked for by the clients and used as the texture name of the players wearing it.
+/// - `texture`: Image file the players wearing it are drawn with, loaded by
+///   every client whatever the map. The path is relative to the client.
+pub struct Skin {
+    pub name: String,
+    pub texture: String,
+}
+
+impl Default for Skin {
+    fn default() -> Self {
+        Self { name: "goblin".to_string(), texture: "assets/img/goblin.png".to_string() }
+    }
+}
+
+impl Skin {
+    /// Reads the list of skins of a JSON file.
+    ///
+    /// # Returns
+    /// - `Err` if the file can't be read or parsed, naming the file and the faulty line.
+    pub fn from_file(path: &str) -> Result<Vec<Self>, Box<dyn Error>> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("can't read skins file \"{}\": {}", path, e))?;
+        let skins = serde_json::from_str(&content)
+            .map_err(|e| format!("invalid skins file \"{}\": {}", path, e))?;
+        Ok(skins)
+    }
+}
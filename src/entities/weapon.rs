@@ -0,0 +1,221 @@
+use std::{error::Error, fs};
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+/// Flight of the projectiles fired by a weapon.
+///
+/// # Fields
+/// - `speed`: Tiles travelled every second.
+/// - `splash`: Radius, in tiles, of the explosion. Only the player hit is hurt if 0.
+/// - `texture`: Name of the texture the projectile is drawn with, which the map must load.
+pub struct Ballistics {
+    pub speed: f32,
+    #[serde(default)]
+    pub splash: f32,
+    pub texture: String,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+/// Definition of a weapon, read from the weapons file of the server.
+///
+/// A field left out of the file takes the value of the default rifle.
+///
+/// # Fields
+/// - `name`: Identifier of the weapon.
+/// - `damage`: Damage of a hit closer than `falloff_start` tiles, for each pellet.
+///   For a projectile, damage at the center of the explosion.
+/// - `min_damage`: Damage of a hit farther than `falloff_end` tiles.
+/// - `falloff_start`: Distance, in tiles, the damage starts decreasing at.
+/// - `falloff_end`: Distance, in tiles, the damage reaches `min_damage` at.
+/// - `fire_rate`: Shots every second.
+/// - `spread`: Angle, in degrees, the pellets are scattered over around the aim.
+/// - `pellets`: Rays cast, or projectiles fired, by a single shot.
+/// - `range`: Distance, in tiles, beyond which a shot hits nothing.
+/// - `ammo`: Shots in a magazine.
+/// - `reload`: Milliseconds needed to refill the magazine.
+/// - `projectile`: Set for the weapons firing projectiles, which explode at
+///   the first wall or player met, or at the end of their range. Hitscan otherwise.
+/// - `starting`: Whether the players spawn with it. The others are picked up on the map.
+pub struct Weapon {
+    pub name: String,
+    pub damage: u32,
+    pub min_damage: u32,
+    pub falloff_start: f32,
+    pub falloff_end: f32,
+    pub fire_rate: f32,
+    pub spread: f32,
+    pub pellets: u32,
+    pub range: f32,
+    pub ammo: u32,
+    pub reload: u64,
+    pub projectile: Option<Ballistics>,
+    pub starting: bool,
+}
+
+impl Default for Weapon {
+    fn default() -> Self {
+        Self {
+            name: "rifle".to_string(),
+            damage: 34,
+            min_damage: 15,
+            falloff_start: 6.0,
+            falloff_end: 20.0,
+            fire_rate: 1.0,
+            spread: 0.0,
+            pellets: 1,
+            range: 50.0,
+            ammo: 10,
+            reload: 2000,
+            projectile: None,
+            starting: false,
+        }
+    }
+}
+
+impl Weapon {
+    /// Reads the list of weapons of a JSON file.
+    ///
+    /// # Returns
+    /// - `Err` if the file can't be read or parsed, naming the file and the faulty line.
+    pub fn from_file(path: &str) -> Result<Vec<Self>, Box<dyn Error>> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("can't read weapons file \"{}\": {}", path, e))?;
+        let weapons = serde_json::from_str(&content)
+            .map_err(|e| format!("invalid weapons file \"{}\": {}", path, e))?;
+        Ok(weapons)
+    }
+
+    /// Damage of a hit at `distance` tiles, decreasing linearly between the falloff distances.
+    pub fn damage_at(&self, distance: f32) -> u32 {
+        if distance <= self.falloff_start || self.falloff_end <= self.falloff_start {
+            return self.damage;
+        }
+        let t = ((distance - self.falloff_start) / (self.falloff_end - self.falloff_start)).min(1.0);
+        let damage = self.damage as f32 + (self.min_damage as f32 - self.damage as f32) * t;
+        damage.round() as u32
+    }
+
+    /// Milliseconds between two shots.
+    pub fn fire_interval(&self) -> u64 {
+        (1000.0 / self.fire_rate) as u64
+    }
+
+    /// Every problem of the definition, empty if it is usable.
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if self.fire_rate <= 0.0 {
+            problems.push(format!("weapon \"{}\": fire_rate must be positive", self.name));
+        }
+        if !(0.0..=180.0).contains(&self.spread) {
+            problems.push(format!("weapon \"{}\": spread must be between 0 and 180 degrees (got {})", self.name, self.spread));
+        }
+        if self.pellets == 0 {
+            problems.push(format!("weapon \"{}\": pellets must be at least 1", self.name));
+        }
+        if self.range <= 0.0 {
+            problems.push(format!("weapon \"{}\": range must be positive", self.name));
+        }
+        if self.min_damage > self.damage {
+            problems.push(format!("weapon \"{}\": min_damage can't be above damage ({} > {})", self.name, self.min_damage, self.damage));
+        }
+        if self.falloff_start < 0.0 || self.falloff_end < self.falloff_start {
+            problems.push(format!("weapon \"{}\": falloff_start can't be negative nor beyond falloff_end (got {} and {})", self.name, self.falloff_start, self.falloff_end));
+        }
+        if self.ammo == 0 {
+            problems.push(format!("weapon \"{}\": ammo must be at least 1", self.name));
+        }
+        if let Some(projectile) = &self.projectile {
+            if projectile.speed <= 0.0 {
+                problems.push(format!("weapon \"{}\": projectile.speed must be positive", self.name));
+            }
+            if projectile.splash < 0.0 {
+                problems.push(format!("weapon \"{}\": projectile.splash can't be negative", self.name));
+            }
+        }
+        problems
+    }
+
+    /// Damage of an explosion at `distance` tiles of its center, decreasing
+    /// linearly down to nothing at the edge of the splash radius.
+    pub fn splash_damage_at(&self, distance: f32) -> u32 {
+        let radius = self.projectile.as_ref().map_or(0.0, |p| p.splash);
+        if radius <= 0.0 {
+            return self.damage;
+        }
+        (self.damage as f32 * (1.0 - distance / radius).max(0.0)).round() as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn damage_is_full_before_the_falloff() {
+        let rifle = Weapon::default();
+        assert_eq!(rifle.damage_at(0.0), 34);
+        assert_eq!(rifle.damage_at(6.0), 34);
+    }
+
+    #[test]
+    fn damage_decreases_linearly_over_the_falloff() {
+        let rifle = Weapon { damage: 40, min_damage: 10, falloff_start: 10.0, falloff_end: 20.0, ..Weapon::default() };
+        assert_eq!(rifle.damage_at(15.0), 25);
+        assert_eq!(rifle.damage_at(20.0), 10);
+        assert_eq!(rifle.damage_at(45.0), 10);
+    }
+
+    #[test]
+    fn damage_is_constant_without_falloff_range() {
+        let rifle = Weapon { falloff_start: 5.0, falloff_end: 5.0, ..Weapon::default() };
+        assert_eq!(rifle.damage_at(30.0), rifle.damage);
+    }
+
+    #[test]
+    fn the_default_rifle_is_valid() {
+        assert!(Weapon::default().problems().is_empty());
+    }
+
+    #[test]
+    fn min_damage_above_damage_is_rejected() {
+        let rifle = Weapon { damage: 10, min_damage: 20, ..Weapon::default() };
+        assert_eq!(rifle.problems(), vec!["weapon \"rifle\": min_damage can't be above damage (20 > 10)".to_string()]);
+    }
+
+    #[test]
+    fn falloff_range_must_be_ordered_and_positive() {
+        let negative = Weapon { falloff_start: -1.0, ..Weapon::default() };
+        assert_eq!(negative.problems().len(), 1);
+        let reversed = Weapon { falloff_start: 20.0, falloff_end: 6.0, ..Weapon::default() };
+        assert_eq!(reversed.problems().len(), 1);
+    }
+
+    #[test]
+    fn every_problem_is_listed() {
+        let broken = Weapon { fire_rate: 0.0, pellets: 0, ammo: 0, range: -1.0, spread: 200.0, ..Weapon::default() };
+        assert_eq!(broken.problems().len(), 5);
+    }
+
+    fn rocket(splash: f32) -> Weapon {
+        let projectile = Ballistics { speed: 10.0, splash, texture: "rocket".to_string() };
+        Weapon { name: "rocket".to_string(), damage: 100, projectile: Some(projectile), ..Weapon::default() }
+    }
+
+    #[test]
+    fn splash_damage_decreases_to_the_edge() {
+        let rocket = rocket(2.0);
+        assert_eq!(rocket.splash_damage_at(0.0), 100);
+        assert_eq!(rocket.splash_damage_at(1.0), 50);
+        assert_eq!(rocket.splash_damage_at(2.0), 0);
+        assert_eq!(rocket.splash_damage_at(3.0), 0);
+    }
+
+    #[test]
+    fn no_splash_only_hurts_the_player_hit() {
+        assert_eq!(rocket(0.0).splash_damage_at(5.0), 100);
+        assert_eq!(Weapon::default().splash_damage_at(5.0), 34);
+    }
+}
@@ -8,10 +8,14 @@ mod rays;
 pub mod frames;
 pub mod utils;
 pub mod camera;
+pub mod clock;
 pub mod server;
 pub mod client;
 pub mod resources;
 pub use resources::{TextureManager,ResourceLoader,ResourceManager,FontManager,FontDetails};
 
+pub mod demo;
+pub mod transport;
+
 mod n_loader;
 pub use n_loader::*;
\ No newline at end of file
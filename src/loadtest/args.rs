@@ -0,0 +1,35 @@
+use std::net::Ipv4Addr;
+pub use clap::Parser;
+
+#[derive(Parser, Debug, Clone)]
+#[command(version, about, long_about = None)]
+/// open simulated client sessions against a multiplayer fps server
+pub struct Args {
+    /// host addr
+    #[arg(long)]
+    pub host: Ipv4Addr,
+
+    /// host port
+    #[arg(long)]
+    pub port: u32,
+
+    /// number of simulated sessions
+    #[arg(short='n',long,default_value_t=8)]
+    pub sessions: usize,
+
+    /// seconds each session stays connected
+    #[arg(short,long,default_value_t=30)]
+    pub duration: u64,
+
+    /// milliseconds between two session starts
+    #[arg(long,default_value_t=100)]
+    pub ramp: u64,
+
+    /// nickname prefix of the sessions, followed by their number
+    #[arg(long,default_value="loadtest")]
+    pub prefix: String,
+
+    /// password of the server, if it asks for one
+    #[arg(long)]
+    pub password: Option<String>,
+}
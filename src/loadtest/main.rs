@@ -0,0 +1,72 @@
+use std::{error::Error, net::SocketAddr, thread, time::Duration};
+use clap::Parser;
+pub mod args;
+pub mod session;
+
+use session::Report;
+
+/// Value at the given percentile of sorted durations, in milliseconds.
+fn percentile(sorted: &[Duration], p: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    let index = ((sorted.len() - 1) as f64 * p / 100.0).round() as usize;
+    sorted[index].as_secs_f64() * 1000.0
+}
+
+fn print_report(reports: &[Report]) {
+    println!(
+        "{:<16} {:>9} {:>11} {:>8} {:>8} {:>8} {:>7} {:>6}  error",
+        "session", "join ms", "snapshot/s", "rtt p50", "rtt p90", "rtt p99", "loss %", "shots"
+    );
+    let mut all_rtt = Vec::new();
+    for r in reports {
+        let mut rtt = r.rtt.clone();
+        rtt.sort();
+        all_rtt.extend(rtt.iter().copied());
+        println!(
+            "{:<16} {:>9} {:>11.1} {:>8.1} {:>8.1} {:>8.1} {:>7.1} {:>6}  {}",
+            r.nickname,
+            r.join_latency.map_or("-".to_string(), |d| format!("{:.1}", d.as_secs_f64() * 1000.0)),
+            r.snapshot_rate(),
+            percentile(&rtt, 50.0),
+            percentile(&rtt, 90.0),
+            percentile(&rtt, 99.0),
+            r.loss(),
+            r.shots,
+            r.error.as_deref().unwrap_or(""),
+        );
+    }
+    all_rtt.sort();
+    let joined = reports.iter().filter(|r| r.join_latency.is_some()).count();
+    let failed = reports.iter().filter(|r| r.error.is_some()).count();
+    println!(
+        "{} sessions, {} joined, {} failed, rtt p50 {:.1}ms p90 {:.1}ms p99 {:.1}ms",
+        reports.len(), joined, failed,
+        percentile(&all_rtt, 50.0), percentile(&all_rtt, 90.0), percentile(&all_rtt, 99.0)
+    );
+}
+
+fn main() -> Result<(),Box<dyn Error>> {
+    let args = args::Args::parse();
+    let server: SocketAddr = format!("{}:{}",args.host,args.port).parse()?;
+    let duration = Duration::from_secs(args.duration);
+    println!("opening {} sessions on {} for {}s", args.sessions, server, args.duration);
+
+    let mut handles = Vec::new();
+    for i in 0..args.sessions {
+        let nickname = format!("{}{}", args.prefix, i);
+        let password = args.password.clone();
+        handles.push(thread::spawn(move || session::run(server, nickname, password, duration)));
+        thread::sleep(Duration::from_millis(args.ramp));
+    }
+    let mut reports = Vec::new();
+    for handle in handles {
+        match handle.join() {
+            Ok(report) => reports.push(report),
+            Err(_) => eprintln!("a session panicked"),
+        }
+    }
+    print_report(&reports);
+    Ok(())
+}
@@ -0,0 +1,196 @@
+use std::{
+    error::Error,
+    net::{SocketAddr, UdpSocket},
+    time::{Duration, Instant},
+};
+
+use multiplayer_fps::{
+    data::{default_addr, Connection, InputData, OutputData, Status, Update},
+    transport::MAX_DATAGRAM,
+    world::Map,
+};
+use rand::prelude::*;
+
+/// Updates sent every second, like a client running at 30 fps.
+const SEND_RATE: u32 = 30;
+/// Time between two pings.
+const PING_INTERVAL: Duration = Duration::from_millis(250);
+/// Time allowed to the server to answer the connection.
+const JOIN_TIMEOUT: Duration = Duration::from_secs(10);
+/// Tiles walked every second.
+const SPEED: f32 = 3.0;
+/// Time between two respawn requests while dead, the server refusing them until the delay is over.
+const RESPAWN_RETRY: Duration = Duration::from_millis(500);
+
+/// What one session measured.
+///
+/// # Fields
+/// - `nickname`: The nickname the session played with.
+/// - `join_latency`: Time between the connection request and the server answer.
+/// - `connected`: Time spent in game.
+/// - `snapshots`: Player updates received from the server.
+/// - `rtt`: Every round trip time measured.
+/// - `pings`: Pings sent, to compute the loss from `rtt.len()`.
+/// - `shots`: Shots fired.
+/// - `error`: Why the session stopped early, if it did.
+pub struct Report {
+    pub nickname: String,
+    pub join_latency: Option<Duration>,
+    pub connected: Duration,
+    pub snapshots: u64,
+    pub rtt: Vec<Duration>,
+    pub pings: u64,
+    pub shots: u64,
+    pub error: Option<String>,
+}
+
+impl Report {
+    fn new(nickname: &str) -> Self {
+        Self { nickname: nickname.to_string(), join_latency: None, connected: Duration::ZERO, snapshots: 0, rtt: Vec::new(), pings: 0, shots: 0, error: None }
+    }
+
+    /// Player updates received every second.
+    pub fn snapshot_rate(&self) -> f64 {
+        let secs = self.connected.as_secs_f64();
+        if secs > 0.0 { self.snapshots as f64 / secs } else { 0.0 }
+    }
+
+    /// Share of the pings that got no answer, in percent.
+    pub fn loss(&self) -> f64 {
+        if self.pings == 0 {
+            return 0.0;
+        }
+        100.0 * (self.pings - self.rtt.len() as u64) as f64 / self.pings as f64
+    }
+}
+
+/// Sends a message to the server.
+fn send(socket: &UdpSocket, data: &InputData) -> Result<(), Box<dyn Error>> {
+    let serialized = serde_json::to_string(data)?;
+    socket.send(serialized.as_bytes())?;
+    Ok(())
+}
+
+/// Reads the next message of the server, if any.
+fn receive(socket: &UdpSocket) -> Result<Option<OutputData>, Box<dyn Error>> {
+    let mut buf = [0u8; MAX_DATAGRAM];
+    let size = match socket.recv(&mut buf) {
+        Ok(s) => s,
+        Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => return Ok(None),
+        Err(e) => return Err(Box::new(e)),
+    };
+    let data = String::from_utf8(buf[..size].to_vec())?;
+    Ok(Some(serde_json::from_str::<OutputData>(&data).unwrap_or(OutputData::Unknown)))
+}
+
+/// Plays one simulated client for `duration` and reports what it measured.
+///
+/// The session does the real handshake, then wanders the map with the same
+/// wall collision as the client, shoots now and then, asks to respawn once
+/// killed, answers the server pings, sends its own to measure the round trip
+/// time, and disconnects.
+pub fn run(server: SocketAddr, nickname: String, password: Option<String>, duration: Duration) -> Report {
+    let mut report = Report::new(&nickname);
+    if let Err(e) = play(server, nickname, password, duration, &mut report) {
+        report.error = Some(e.to_string());
+    }
+    report
+}
+
+fn play(server: SocketAddr, nickname: String, password: Option<String>, duration: Duration, report: &mut Report) -> Result<(), Box<dyn Error>> {
+    let socket = UdpSocket::bind("0.0.0.0:0")?;
+    socket.connect(server)?;
+    socket.set_nonblocking(true)?;
+
+    // Handshake
+    let start = Instant::now();
+    send(&socket, &InputData::Connection(Connection { addr: default_addr(), nickname: nickname.clone(), password, team: None, skin: None }))?;
+    let (player, loader) = loop {
+        if start.elapsed() > JOIN_TIMEOUT {
+            return Err("no answer to the connection".into());
+        }
+        match receive(&socket)? {
+            Some(OutputData::Connecting((player, _, loader))) => break (player, loader),
+            Some(OutputData::AccessDeny(deny)) => return Err(format!("access denied: {}", deny.reason).into()),
+            _ => std::thread::sleep(Duration::from_millis(1)),
+        }
+    };
+    report.join_latency = Some(start.elapsed());
+
+    let mut map = Map::from(&loader);
+    let mut rng = rand::rng();
+    let (mut x, mut y, mut d) = (player.x, player.y, player.d);
+    let step = Duration::from_secs_f64(1.0 / SEND_RATE as f64);
+    let epoch = Instant::now();
+    let mut last_ping = Instant::now() - PING_INTERVAL;
+    let mut next_shot = Instant::now() + Duration::from_millis(rng.random_range(1000..5000));
+    let mut dead = false;
+    let mut last_respawn = Instant::now() - RESPAWN_RETRY;
+    while epoch.elapsed() < duration {
+        let frame = Instant::now();
+
+        // Wander: walk straight and turn when a wall is ahead
+        let nx = x + d.cos() * SPEED * step.as_secs_f32();
+        let ny = y + d.sin() * SPEED * step.as_secs_f32();
+        match map.is_wall(nx.floor() as i32, ny.floor() as i32) {
+            Some(false) => (x, y) = (nx, ny),
+            _ => d += rng.random_range(std::f32::consts::FRAC_PI_2..std::f32::consts::PI * 1.5),
+        }
+        if rng.random_bool(0.02) {
+            d += rng.random_range(-0.5..0.5);
+        }
+        // Like the client, a dead session sends no inputs until it respawns
+        if !dead {
+            send(&socket, &InputData::Update(Update::new(default_addr(), nickname.clone(), (x, y, d))))?;
+        }
+        if !dead && frame >= next_shot {
+            send(&socket, &InputData::Shoot(Update::new(default_addr(), nickname.clone(), (x, y, d))))?;
+            report.shots += 1;
+            next_shot = frame + Duration::from_millis(rng.random_range(1000..5000));
+        }
+        if dead && last_respawn.elapsed() >= RESPAWN_RETRY {
+            last_respawn = Instant::now();
+            send(&socket, &InputData::Respawn { addr: default_addr() })?;
+        }
+        if last_ping.elapsed() >= PING_INTERVAL {
+            last_ping = Instant::now();
+            send(&socket, &InputData::Ping { addr: default_addr(), time: epoch.elapsed().as_millis() as u64 })?;
+            report.pings += 1;
+        }
+
+        while let Some(output) = receive(&socket)? {
+            match output {
+                OutputData::Update(data) if data.nickname == nickname => {
+                    match data.status {
+                        Some(Status::Dead(_)) => dead = true,
+                        Some(Status::Alive) => dead = false,
+                        _ => (),
+                    }
+                    // Respawned: the server moved us to a spawn point
+                    if let (Some(nx), Some(ny)) = (data.x, data.y) {
+                        (x, y) = (nx, ny);
+                    }
+                },
+                OutputData::Update(_) => report.snapshots += 1,
+                OutputData::Ping(time) => send(&socket, &InputData::Pong { addr: default_addr(), time })?,
+                OutputData::Pong { client, .. } => {
+                    let now = epoch.elapsed().as_millis() as u64;
+                    report.rtt.push(Duration::from_millis(now.saturating_sub(client)));
+                },
+                OutputData::MapChange((player, _, loader)) => {
+                    map = Map::from(&loader);
+                    (x, y, d) = (player.x, player.y, player.d);
+                    dead = player.status != Status::Alive;
+                },
+                OutputData::AccessDeny(deny) => return Err(format!("disconnected by the server: {}", deny.reason).into()),
+                _ => (),
+            }
+        }
+        report.connected = epoch.elapsed();
+        if let Some(rest) = step.checked_sub(frame.elapsed()) {
+            std::thread::sleep(rest);
+        }
+    }
+    send(&socket, &InputData::Disconnection { addr: default_addr() })?;
+    Ok(())
+}
@@ -2,7 +2,7 @@ use sdl2::rect::Point;
 use serde::{Deserialize, Serialize};
 use std::{collections::HashMap, fs::File, io::Read};
 
-use crate::{resources::FontDetails, world::{Map, Tile}};
+use crate::{data::{ItemKind, Team}, resources::FontDetails, world::{Map, Tile}};
 
 /// Alias for a named identifier, typically used for assets (e.g. texture names).
 type Name = String;
@@ -16,7 +16,7 @@ type Size = u16;
 /// Main structure representing a game map configuration.
 ///
 /// Contains layout data, texture associations, and spawn points.
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
 pub struct Loader {
     /// 2D grid representing the map layout.
     /// Each `u8` corresponds to a tile ID.
@@ -32,21 +32,69 @@ pub struct Loader {
     /// List of spawn points on the map.
     pub spawnpoints: Vec<SpawnPoint>,
 
+    /// Flag bases of the capture-the-flag mode, one per team.
+    #[serde(default)]
+    pub flags: Vec<FlagBase>,
+
+    /// Items the players pick up by walking over them.
+    #[serde(default)]
+    pub items: Vec<ItemSpawn>,
+
     resources: Resources,
 }
 
 /// Represents a spawn point position on the map.
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
 pub struct SpawnPoint {
     /// X coordinate in tile units.
     pub x: u8,
 
     /// Y coordinate in tile units.
     pub y: u8,
+
+    /// Team spawning here in the team modes. Any team can use it if unset.
+    #[serde(default)]
+    pub team: Option<Team>,
+}
+
+/// Tile a team's flag stands on in the capture-the-flag mode.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct FlagBase {
+    pub team: Team,
+
+    /// X coordinate in tile units.
+    pub x: u8,
+
+    /// Y coordinate in tile units.
+    pub y: u8,
+}
+
+fn default_item_respawn() -> u64 {
+    20000
+}
+
+/// Tile an item lies on.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct ItemSpawn {
+    /// What picking it up gives.
+    pub item: ItemKind,
+
+    /// X coordinate in tile units.
+    pub x: u8,
+
+    /// Y coordinate in tile units.
+    pub y: u8,
+
+    /// Milliseconds before it comes back once taken. 20 seconds by default.
+    #[serde(default = "default_item_respawn")]
+    pub respawn: u64,
+
+    /// Name of the texture it is drawn with.
+    pub texture: Name,
 }
 
 /// Structure holding paths to game resources like textures and fonts.
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
 pub struct Resources {
     /// Base directory containing textures.
     textures_directory: Path,
@@ -61,7 +109,7 @@ pub struct Resources {
     fonts: HashMap<Name, Fonts>,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
 pub struct Fonts {
     pub path: Path,
     pub size: u16,
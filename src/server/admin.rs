@@ -0,0 +1,197 @@
+use std::{
+    error::Error,
+    io::{BufRead, BufReader, Read, Write},
+    net::{IpAddr, Ipv4Addr, SocketAddr, TcpListener, TcpStream},
+    sync::mpsc::{channel, Receiver, Sender},
+    thread,
+    time::Duration,
+};
+
+use crate::data::{Stats, Status, Team};
+use serde::{Deserialize, Serialize};
+
+/// Maximum time a control connection waits for the simulation to answer.
+const REPLY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Longest request line accepted, in bytes.
+const MAX_LINE: u64 = 16 * 1024;
+
+/// Failed authentications after which a control connection is closed.
+const MAX_FAILURES: u32 = 3;
+
+/// A command paired with the channel its answer must be sent on.
+pub type Request = (AdminCommand, Sender<AdminResponse>);
+
+/// Receiving end of the admin command queue, drained by the simulation loop.
+pub type AdminQueue = Receiver<Request>;
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(tag = "type", content = "data")]
+/// Commands accepted on the control socket.
+pub enum AdminCommand {
+    /// Report the map, uptime and connected players.
+    Status,
+    /// Disconnect a player.
+    Kick { nickname: String, reason: Option<String> },
+    /// Disconnect a player and refuse any further connection from its ip.
+    Ban { nickname: String, reason: Option<String> },
+    /// Remove an ip from the ban list.
+    Unban { ip: IpAddr },
+    /// Load another map file and move every player onto it.
+    ChangeMap { path: String },
+    /// Load the next map of the rotation.
+    NextMap,
+    /// Send a text message to every connected player.
+    Broadcast { message: String },
+}
+
+#[derive(Deserialize, Debug)]
+/// One line sent by a control client.
+///
+/// # Example
+/// ```json
+/// {"password":"secret","command":{"type":"Kick","data":{"nickname":"bob","reason":null}}}
+/// ```
+pub struct AdminRequest {
+    pub password: String,
+    pub command: AdminCommand,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(tag = "type", content = "data")]
+/// One line sent back to a control client.
+pub enum AdminResponse {
+    Status(ServerStatus),
+    Done(String),
+    Error(String),
+}
+
+#[derive(Serialize, Debug)]
+/// Snapshot of the server returned by `AdminCommand::Status`.
+pub struct ServerStatus {
+    pub name: String,
+    pub map: String,
+    pub rotation: Vec<String>,
+    /// Seconds since the server started.
+    pub uptime: u64,
+    pub max_hosts: u8,
+    pub players: Vec<PlayerStatus>,
+    pub bans: Vec<IpAddr>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct PlayerStatus {
+    pub nickname: String,
+    pub addr: SocketAddr,
+    pub x: f32,
+    pub y: f32,
+    pub status: Status,
+    pub bot: bool,
+    pub team: Option<Team>,
+    pub stats: Stats,
+}
+
+/// Opens the control socket on `127.0.0.1:port`.
+///
+/// Every accepted connection is served on its own thread. Requests are JSON
+/// objects, one per line, and each one gets a single JSON line as answer.
+/// Authenticated commands are pushed on the returned queue: the game state is
+/// only ever touched by the simulation loop draining it.
+///
+/// # Arguments
+/// * `port` - Local TCP port to listen on.
+/// * `password` - Password every request must carry.
+///
+/// # Returns
+/// * `Ok(AdminQueue)` - The queue to drain from the simulation loop.
+/// * `Err` if the port can't be bound.
+pub fn listen(port: u16, password: String) -> std::io::Result<AdminQueue> {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))?;
+    info!("admin socket listening on {}", listener.local_addr()?);
+    let (tx, rx) = channel::<Request>();
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let stream = match stream {
+                Ok(s) => s,
+                Err(e) => {
+                    warn!("admin socket: {}", e);
+                    continue;
+                }
+            };
+            let tx = tx.clone();
+            let password = password.clone();
+            thread::spawn(move || {
+                if let Err(e) = serve(stream, tx, &password) {
+                    debug!("admin connection closed: {}", e);
+                }
+            });
+        }
+    });
+    Ok(rx)
+}
+
+/// Reads requests from a control connection until it is closed.
+///
+/// The connection is closed on a line longer than `MAX_LINE` bytes or after
+/// `MAX_FAILURES` failed authentications.
+fn serve(stream: TcpStream, queue: Sender<Request>, password: &str) -> Result<(), Box<dyn Error>> {
+    let mut writer = stream.try_clone()?;
+    let mut reader = BufReader::new(stream);
+    let mut failures = 0;
+    loop {
+        let mut line = String::new();
+        if reader.by_ref().take(MAX_LINE).read_line(&mut line)? == 0 {
+            return Ok(());
+        }
+        if !line.ends_with('\n') && line.len() as u64 >= MAX_LINE {
+            respond(&mut writer, &AdminResponse::Error(format!("request longer than {} bytes", MAX_LINE)))?;
+            return Err(format!("request longer than {} bytes", MAX_LINE).into());
+        }
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = match serde_json::from_str::<AdminRequest>(&line) {
+            Ok(request) if !same_password(&request.password, password) => {
+                failures += 1;
+                AdminResponse::Error("authentication failed".to_string())
+            },
+            Ok(request) => forward(&queue, request.command),
+            Err(e) => AdminResponse::Error(format!("malformed request: {}", e)),
+        };
+        respond(&mut writer, &response)?;
+        if failures >= MAX_FAILURES {
+            return Err(format!("{} failed authentications", failures).into());
+        }
+    }
+}
+
+/// Writes `response` as one JSON line.
+fn respond(writer: &mut TcpStream, response: &AdminResponse) -> Result<(), Box<dyn Error>> {
+    let serialized = serde_json::to_string(response)?;
+    writer.write_all(serialized.as_bytes())?;
+    writer.write_all(b"\n")?;
+    Ok(())
+}
+
+/// Compares two passwords in a time only depending on their lengths, not on
+/// the position of the first differing byte.
+fn same_password(given: &str, expected: &str) -> bool {
+    let (given, expected) = (given.as_bytes(), expected.as_bytes());
+    let mut diff = given.len() ^ expected.len();
+    for (i, byte) in expected.iter().enumerate() {
+        diff |= (given.get(i).copied().unwrap_or(0) ^ byte) as usize;
+    }
+    diff == 0
+}
+
+/// Pushes a command on the queue and waits for the simulation to answer it.
+fn forward(queue: &Sender<Request>, command: AdminCommand) -> AdminResponse {
+    let (reply_tx, reply_rx) = channel();
+    if queue.send((command, reply_tx)).is_err() {
+        return AdminResponse::Error("server is shutting down".to_string());
+    }
+    match reply_rx.recv_timeout(REPLY_TIMEOUT) {
+        Ok(response) => response,
+        Err(_) => AdminResponse::Error("no answer from the simulation".to_string()),
+    }
+}
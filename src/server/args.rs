@@ -1,17 +1,72 @@
 pub use clap::Parser;
 
+use super::{config::{GameMode, SpawnStrategy}, log::LogLevel};
+
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
 /// launch the multiplayer fps server
 pub struct Args {
-    /// maximum number of hosts on the session
-    #[arg(long="max-hosts",default_value_t=4)]
-    pub max_hosts: u8,
+    /// json configuration file. flags given on the command line override it
+    #[arg(short,long)]
+    pub config: Option<String>,
+
+    /// name of the server
+    #[arg(long)]
+    pub name: Option<String>,
+
+    /// password asked to joining players
+    #[arg(long)]
+    pub password: Option<String>,
+
+    /// maximum number of hosts on the session. 4 by default
+    #[arg(long="max-hosts")]
+    pub max_hosts: Option<u8>,
 
     /// port number of the server. is random by default
-    #[arg(short,long,default_value_t=0)]
-    pub port: u32,
+    #[arg(short,long)]
+    pub port: Option<u32>,
 
+    /// first map played. defaults to the first map of the rotation
     #[arg(short,long)]
-    pub map: String,
+    pub map: Option<String>,
+
+    /// comma separated map files played one after the other
+    #[arg(long,value_delimiter=',')]
+    pub rotation: Vec<String>,
+
+    /// simulation ticks per second. 30 by default
+    #[arg(long="tick-rate")]
+    pub tick_rate: Option<u32>,
+
+    /// rules of the match. deathmatch by default
+    #[arg(long)]
+    pub mode: Option<GameMode>,
+
+    /// spawn point choice of the game mode played. scored by default, random in capture the flag
+    #[arg(long)]
+    pub spawns: Option<SpawnStrategy>,
+
+    /// players kept on the server by adding bots. 0 by default
+    #[arg(long)]
+    pub bots: Option<u8>,
+
+    /// verbosity of the server output. info by default
+    #[arg(long="log-level")]
+    pub log_level: Option<LogLevel>,
+
+    /// local port of the admin control socket. disabled by default
+    #[arg(long="admin-port")]
+    pub admin_port: Option<u16>,
+
+    /// password required by the admin control socket
+    #[arg(long="admin-password")]
+    pub admin_password: Option<String>,
+
+    /// record the match to this demo file
+    #[arg(long)]
+    pub record: Option<String>,
+
+    /// local port of the prometheus metrics exporter. disabled by default
+    #[arg(long="metrics-port")]
+    pub metrics_port: Option<u16>,
 }
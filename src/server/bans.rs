@@ -0,0 +1,73 @@
+use std::{collections::HashSet, error::Error, fs, net::IpAddr, path::Path};
+
+/// Addresses refused by the server, optionally backed by a file.
+///
+/// The file holds one ip per line. Empty lines and lines starting with `#`
+/// are ignored. It is rewritten every time the list changes.
+pub struct BanList {
+    path: Option<String>,
+    ips: HashSet<IpAddr>,
+}
+
+impl BanList {
+    /// Creates a ban list, reading `path` if it exists.
+    ///
+    /// # Returns
+    /// * `Err` if the file can't be read or holds an invalid ip.
+    pub fn new(path: Option<String>) -> Result<Self, Box<dyn Error>> {
+        let mut ips = HashSet::new();
+        if let Some(path) = &path {
+            if Path::new(path).exists() {
+                for (n, line) in fs::read_to_string(path)?.lines().enumerate() {
+                    let line = line.trim();
+                    if line.is_empty() || line.starts_with('#') {
+                        continue;
+                    }
+                    let ip = line.parse::<IpAddr>()
+                        .map_err(|e| format!("ban list \"{}\" line {}: {}", path, n + 1, e))?;
+                    ips.insert(ip);
+                }
+            }
+        }
+        Ok(Self { path, ips })
+    }
+
+    pub fn contains(&self, ip: &IpAddr) -> bool {
+        self.ips.contains(ip)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &IpAddr> {
+        self.ips.iter()
+    }
+
+    /// Bans an ip and saves the list.
+    pub fn insert(&mut self, ip: IpAddr) -> Result<(), Box<dyn Error>> {
+        self.ips.insert(ip);
+        self.save()
+    }
+
+    /// Unbans an ip and saves the list.
+    ///
+    /// # Returns
+    /// * `Ok(false)` if the ip was not banned.
+    pub fn remove(&mut self, ip: &IpAddr) -> Result<bool, Box<dyn Error>> {
+        if !self.ips.remove(ip) {
+            return Ok(false);
+        }
+        self.save()?;
+        Ok(true)
+    }
+
+    fn save(&self) -> Result<(), Box<dyn Error>> {
+        let path = match &self.path {
+            Some(p) => p,
+            None => return Ok(()),
+        };
+        let mut content = String::new();
+        for ip in &self.ips {
+            content.push_str(&format!("{}\n", ip));
+        }
+        fs::write(path, content)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,206 @@
+use std::{collections::HashMap, error::Error};
+
+use crate::{data::{default_addr, GameEvent, Loadout, Status, Update}, entities::{Player, Players}, world::Map};
+use rand::prelude::*;
+
+use super::config::Config;
+use super::net::Outbox;
+use super::logic::{self, Session};
+use super::teams;
+
+/// Tiles walked by a bot every second.
+const BOT_SPEED: f32 = 3.0;
+/// Longest time step simulated at once, so a late tick doesn't teleport the bots.
+const MAX_STEP: f32 = 0.1;
+
+/// What a bot remembers between two ticks.
+///
+/// # Fields
+/// - `path`: Tiles left to walk through, the next one first.
+/// - `target`: Nickname of the enemy being tracked.
+/// - `seen_since`: Server time `target` came into sight at.
+/// - `next_shot`: Earliest server time the bot can fire again at.
+#[derive(Default)]
+struct Brain {
+    path: Vec<(i32,i32)>,
+    target: Option<String>,
+    seen_since: Option<u64>,
+    next_shot: Option<u64>,
+}
+
+/// State of the bots of a session, indexed by nickname.
+pub struct Bots {
+    brains: HashMap<String, Brain>,
+    last_think: u64,
+}
+
+impl Default for Bots {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Bots {
+    pub fn new() -> Self {
+        Self { brains: HashMap::new(), last_think: 0 }
+    }
+
+    /// Forgets every path and target, for instance after a map change.
+    pub fn reset(&mut self) {
+        self.brains.clear();
+    }
+}
+
+/// Adds or removes bots so the session holds `config.bots.count` players.
+///
+/// Humans always keep their slots: bots only fill what's left below `max_hosts`.
+pub fn fill(session: &mut Session,out: &Outbox,config: &Config) -> Result<(),Box<dyn Error>> {
+    let humans = session.players.iter().filter(|p| !p.bot).count();
+    let bots = session.players.len() - humans;
+    let wanted = (config.bots.count as usize).min(config.max_hosts as usize).saturating_sub(humans);
+    for _ in bots..wanted {
+        add(session, config, out)?;
+    }
+    for _ in wanted..bots {
+        make_room(session, out)?;
+    }
+    Ok(())
+}
+
+/// Spawns a new bot wearing a random skin and holding a random starting weapon,
+/// in the smallest team in the team modes, under spawn protection. The clients
+/// are told about it once it enters their view.
+fn add(session: &mut Session,config: &Config,out: &Outbox) -> Result<(),Box<dyn Error>> {
+    let mut id = 1;
+    while session.players.get_by_nickname(&format!("bot{}", id)).is_some() {
+        id += 1;
+    }
+    let team = config.game_mode.teams().then(|| teams::assign(&session.players, None));
+    let skin = session.skins.choose(&mut rand::rng()).cloned().unwrap_or_default();
+    let mut bot = Player::new(format!("bot{}", id), (0.0,0.0,0.0), skin.name);
+    bot.bot = true;
+    bot.team = team;
+    let now = session.now_ms();
+    let (x, y) = match session.spawns.pick(&session.loader, &session.map, &session.players, &bot, now) {
+        Some(v) => v,
+        // Every spawn point is occupied, the bot is added on a later tick
+        None => return Ok(()),
+    };
+    bot.x = x;
+    bot.y = y;
+    bot.loadout = Loadout::new(&session.weapons);
+    let owned: Vec<usize> = (0..session.weapons.len()).filter(|w| bot.loadout.owns(*w)).collect();
+    if let Some(weapon) = owned.choose(&mut rand::rng()) {
+        bot.loadout.weapon = *weapon;
+    }
+    session.players.push(bot.clone());
+    logic::protect(session, &bot.nickname, out)?;
+    logic::announce(&session.players, out, GameEvent::Join(bot.nickname.clone()))?;
+    info!("{} joined", bot.nickname);
+    Ok(())
+}
+
+/// Removes the last bot of the session to free its slot.
+///
+/// # Returns
+/// * `Ok(false)` if there was no bot to remove.
+pub fn make_room(session: &mut Session,out: &Outbox) -> Result<bool,Box<dyn Error>> {
+    let index = match session.players.iter().rposition(|p| p.bot) {
+        Some(i) => i,
+        None => return Ok(false),
+    };
+    let nickname = session.players[index].nickname.clone();
+    logic::kick(&mut session.players, out, index, "slot given to a player".to_string())?;
+    session.bots.brains.remove(&nickname);
+    info!("{} left", nickname);
+    Ok(true)
+}
+
+/// Moves every bot and fires at the enemies they see, teammates excluded.
+/// The position of a bot is only sent when it changed.
+///
+/// A bot keeps wandering along a path to a random tile until an enemy comes
+/// into sight. It then turns toward it and, once `reaction_time` has passed,
+/// fires through `logic::shoot` with a random aim error of up to `aim_error`
+/// degrees, at most once every `fire_interval` and as fast as its weapon allows.
+pub fn think(session: &mut Session,out: &Outbox,config: &Config) -> Result<(),Box<dyn Error>> {
+    let now = session.now_ms();
+    let dt = (now.saturating_sub(session.bots.last_think) as f32 / 1000.0).min(MAX_STEP);
+    session.bots.last_think = now;
+    let nicknames: Vec<String> = session.players.iter().filter(|p| p.bot).map(|p| p.nickname.clone()).collect();
+    session.bots.brains.retain(|nickname, _| nicknames.contains(nickname));
+    let mut rng = rand::rng();
+    let rules = &config.bots;
+    for nickname in nicknames {
+        let bot = match session.players.get_by_nickname(&nickname) {
+            Some(i) => session.players[i].clone(),
+            None => continue,
+        };
+        if matches!(bot.status, Status::Dead(_)) {
+            continue;
+        }
+        let brain = session.bots.brains.entry(nickname.clone()).or_default();
+        let (mut x, mut y, mut d) = (bot.x, bot.y, bot.d);
+        match visible_enemy(&session.players, &session.map, &bot, rules.view_distance) {
+            Some(enemy) => {
+                if brain.target.as_ref() != Some(&enemy.nickname) {
+                    brain.target = Some(enemy.nickname.clone());
+                    brain.seen_since = Some(now);
+                }
+                d = (enemy.y - y).atan2(enemy.x - x);
+                let reacted = brain.seen_since.is_some_and(|t| now.saturating_sub(t) >= rules.reaction_time);
+                let loaded = brain.next_shot.is_none_or(|t| now >= t);
+                if reacted && loaded {
+                    brain.next_shot = Some(now + rules.fire_interval);
+                    let error = rules.aim_error.to_radians();
+                    let aim = d + rng.random_range(-error..=error);
+                    logic::shoot(session, config, Update::new(default_addr(), nickname.clone(), (x,y,aim)), out)?;
+                }
+            },
+            None => {
+                brain.target = None;
+                brain.seen_since = None;
+                let tile = (x.floor() as i32, y.floor() as i32);
+                // A respawn moves the bot away from its path
+                if brain.path.first().is_some_and(|next| (next.0 - tile.0).abs() + (next.1 - tile.1).abs() > 1) {
+                    brain.path.clear();
+                }
+                if brain.path.is_empty() {
+                    if let Some(goal) = session.map.walkable_tiles().choose(&mut rng) {
+                        brain.path = session.map.find_path(tile, *goal).unwrap_or_default();
+                    }
+                }
+                if let Some(&(tx, ty)) = brain.path.first() {
+                    let (dx, dy) = (tx as f32 + 0.5 - x, ty as f32 + 0.5 - y);
+                    let dist = (dx * dx + dy * dy).sqrt();
+                    let step = BOT_SPEED * dt;
+                    if dist > 0.0 {
+                        d = dy.atan2(dx);
+                    }
+                    if dist <= step {
+                        (x, y) = (tx as f32 + 0.5, ty as f32 + 0.5);
+                        brain.path.remove(0);
+                    } else {
+                        x += dx / dist * step;
+                        y += dy / dist * step;
+                    }
+                }
+            },
+        }
+        // A bot standing still costs no traffic
+        if (x, y, d) != (bot.x, bot.y, bot.d) {
+            logic::update(&mut session.players, &session.interest, Update::new(default_addr(), nickname, (x,y,d)), out)?;
+        }
+    }
+    Ok(())
+}
+
+/// Closest living enemy `bot` can see within `view_distance` tiles.
+fn visible_enemy(players: &Players,map: &Map,bot: &Player,view_distance: f32) -> Option<Player> {
+    players.iter()
+        .filter(|p| p.nickname != bot.nickname && p.status == Status::Alive && !teams::allies(p.team, bot.team))
+        .map(|p| (p, (p.x - bot.x).hypot(p.y - bot.y)))
+        .filter(|(p, dist)| *dist <= view_distance && map.line_of_sight((bot.x, bot.y), (p.x, p.y)))
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(p, _)| p.clone())
+}
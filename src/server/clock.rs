@@ -0,0 +1,41 @@
+use std::{sync::{atomic::{AtomicU64, Ordering}, Arc}, time::{Duration, Instant}};
+
+#[derive(Clone, Debug)]
+/// Source of the server time: the milliseconds elapsed since the server
+/// started, which every timestamp of the protocol is in.
+///
+/// A `Manual` clock only moves when told to, so a session can be stepped
+/// through tick by tick, as the tests do. Clones share the same time.
+pub enum Clock {
+    /// The real time elapsed since the given start.
+    Real(Instant),
+    /// A time moved by hand with `advance`.
+    Manual(Arc<AtomicU64>),
+}
+
+impl Clock {
+    /// A clock starting now and following the real time.
+    pub fn real() -> Self {
+        Self::Real(Instant::now())
+    }
+
+    /// A clock stopped at 0 until moved with `advance`.
+    pub fn manual() -> Self {
+        Self::Manual(Arc::new(AtomicU64::new(0)))
+    }
+
+    /// Milliseconds elapsed since the server started.
+    pub fn now_ms(&self) -> u64 {
+        match self {
+            Self::Real(started) => started.elapsed().as_millis() as u64,
+            Self::Manual(now) => now.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Moves a manual clock forward by `elapsed`. A real clock ignores it.
+    pub fn advance(&self, elapsed: Duration) {
+        if let Self::Manual(now) = self {
+            now.fetch_add(elapsed.as_millis() as u64, Ordering::Relaxed);
+        }
+    }
+}
@@ -0,0 +1,587 @@
+use std::{error::Error, fs, path::Path, time::Duration};
+
+use crate::{data::{ItemKind, Team}, entities::{Skin, Weapon}, Loader};
+use serde::{Deserialize, Serialize};
+
+use super::{args::Args, log::LogLevel};
+
+const DEFAULT_MAX_HOSTS: u8 = 4;
+const DEFAULT_TICK_RATE: u32 = 30;
+const MAX_TICK_RATE: u32 = 1000;
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+#[serde(rename_all = "snake_case")]
+/// Rules of the match played on the server.
+pub enum GameMode {
+    /// Every player for themselves.
+    Deathmatch,
+    /// Two teams, the kills of its players counting for their team.
+    TeamDeathmatch,
+    /// Two teams scoring by bringing the enemy flag to their own base.
+    CaptureTheFlag,
+}
+
+impl GameMode {
+    /// Whether the players are split into teams.
+    pub fn teams(self) -> bool {
+        match self {
+            GameMode::Deathmatch => false,
+            GameMode::TeamDeathmatch | GameMode::CaptureTheFlag => true,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+#[serde(rename_all = "snake_case")]
+/// How a spawn point is picked among the free ones.
+pub enum SpawnStrategy {
+    /// Any of them.
+    Random,
+    /// The farthest from the enemies.
+    Farthest,
+    /// The best scored by distance to the enemies, line of sight from them and recent use.
+    Scored,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct Timeouts {
+    /// Seconds without any packet before a player is disconnected.
+    pub idle: u64,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct RateLimits {
+    /// Packets accepted from one address every second, the others are dropped.
+    pub packets_per_second: u32,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct MetricsConfig {
+    /// Local port of the Prometheus exporter. Disabled if unset.
+    pub port: Option<u16>,
+    /// Seconds between two summary lines in the log. Disabled if unset.
+    pub summary_interval: Option<u64>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct RespawnConfig {
+    /// Milliseconds a killed player stays dead before it can respawn.
+    pub delay: u64,
+    /// Respawn the players as soon as the delay is over, instead of waiting for them to ask.
+    pub auto: bool,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+/// Filtering of the players sent to each client.
+pub struct InterestConfig {
+    /// Only send the players a client could see or hear. Everyone is sent if disabled.
+    pub enabled: bool,
+    /// Tiles under which a player is sent even behind a wall.
+    pub hearing_distance: f32,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+/// Server-side players filling the empty slots.
+pub struct BotConfig {
+    /// Players the server tries to keep, bots leave as humans join.
+    pub count: u8,
+    /// Milliseconds a bot needs to react to an enemy coming into sight.
+    pub reaction_time: u64,
+    /// Largest aim error of a shot, in degrees.
+    pub aim_error: f32,
+    /// Milliseconds between two shots of the same bot.
+    pub fire_interval: u64,
+    /// Tiles beyond which a bot doesn't notice an enemy.
+    pub view_distance: f32,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+/// Choice of the spawn points. Spawn points with a living player on them are never picked.
+pub struct SpawnConfig {
+    /// Strategy of the deathmatch mode.
+    pub deathmatch: SpawnStrategy,
+    /// Strategy of the team deathmatch mode.
+    pub team_deathmatch: SpawnStrategy,
+    /// Strategy of the capture-the-flag mode.
+    pub capture_the_flag: SpawnStrategy,
+    /// Milliseconds during which a spawn point just used is avoided by the scored strategy.
+    pub reuse_delay: u64,
+    /// Milliseconds a spawned player can't be hurt, ending early once it fires. Disabled if 0.
+    pub protection: u64,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+/// Rules of the team modes.
+pub struct TeamConfig {
+    /// Whether shots hurt the players of the shooter's team.
+    pub friendly_fire: bool,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+/// Rules of the capture-the-flag mode.
+pub struct FlagConfig {
+    /// Milliseconds a dropped flag lies on the ground before going back to its base.
+    pub return_delay: u64,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+/// Lifecycle of a match: warmup, match, intermission, then the next map.
+pub struct MatchConfig {
+    /// Human players needed to start the warmup countdown, bots not counted.
+    pub min_players: u8,
+    /// Seconds of warmup before the match starts.
+    pub warmup: u64,
+    /// Score of a player, or of a team in the team modes, ending the match. No limit if 0.
+    pub frag_limit: u32,
+    /// Seconds the match lasts. No limit if 0.
+    pub time_limit: u64,
+    /// Seconds the results are shown before the next map.
+    pub intermission: u64,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct AdminConfig {
+    /// Local port of the control socket.
+    pub port: u16,
+    pub password: String,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+/// Server configuration, read from a JSON file and overridden by the command line.
+///
+/// # Example
+/// ```json
+/// {
+///     "name": "my server",
+///     "map": "conf/map1.json",
+///     "rotation": ["conf/map1.json", "conf/map2.json"],
+///     "tick_rate": 30,
+///     "ban_list": "conf/bans.txt"
+/// }
+/// ```
+pub struct Config {
+    /// Name shown to administrators and in the logs.
+    pub name: String,
+    /// Password asked to joining players. Anyone can join if unset.
+    pub password: Option<String>,
+    pub max_hosts: u8,
+    /// UDP port of the server, random if 0.
+    pub port: u32,
+    /// First map played. Defaults to the first map of the rotation.
+    pub map: Option<String>,
+    pub rotation: Vec<String>,
+    /// Simulation ticks per second.
+    pub tick_rate: u32,
+    pub game_mode: GameMode,
+    pub teams: TeamConfig,
+    pub flags: FlagConfig,
+    /// File the weapon definitions are read from. The players only get the default rifle if unset.
+    pub weapons: Option<String>,
+    /// File the skins the players can wear are read from, the first one being
+    /// given to those asking for none or an unknown one. Everyone is a goblin if unset.
+    pub skins: Option<String>,
+    pub timeouts: Timeouts,
+    pub rate_limits: RateLimits,
+    /// File the bans are read from and saved to. Bans are lost on exit if unset.
+    pub ban_list: Option<String>,
+    pub log_level: LogLevel,
+    pub admin: Option<AdminConfig>,
+    pub metrics: MetricsConfig,
+    /// Demo file the matches are recorded to. Nothing is recorded if unset.
+    pub demo: Option<String>,
+    pub bots: BotConfig,
+    pub interest: InterestConfig,
+    pub respawn: RespawnConfig,
+    pub spawns: SpawnConfig,
+    #[serde(rename = "match")]
+    pub match_rules: MatchConfig,
+}
+
+impl Default for Timeouts {
+    fn default() -> Self {
+        Self { idle: 30 }
+    }
+}
+
+impl Default for RateLimits {
+    fn default() -> Self {
+        Self { packets_per_second: 200 }
+    }
+}
+
+impl Default for RespawnConfig {
+    fn default() -> Self {
+        Self { delay: 3000, auto: false }
+    }
+}
+
+impl Default for SpawnConfig {
+    fn default() -> Self {
+        Self { deathmatch: SpawnStrategy::Scored, team_deathmatch: SpawnStrategy::Scored, capture_the_flag: SpawnStrategy::Random, reuse_delay: 5000, protection: 2000 }
+    }
+}
+
+impl SpawnConfig {
+    /// Strategy of the game mode `mode`.
+    pub fn strategy(&self, mode: GameMode) -> SpawnStrategy {
+        match mode {
+            GameMode::Deathmatch => self.deathmatch,
+            GameMode::TeamDeathmatch => self.team_deathmatch,
+            GameMode::CaptureTheFlag => self.capture_the_flag,
+        }
+    }
+}
+
+impl Default for InterestConfig {
+    fn default() -> Self {
+        Self { enabled: true, hearing_distance: 3.0 }
+    }
+}
+
+impl Default for BotConfig {
+    fn default() -> Self {
+        Self { count: 0, reaction_time: 400, aim_error: 5.0, fire_interval: 1000, view_distance: 20.0 }
+    }
+}
+
+impl Default for FlagConfig {
+    fn default() -> Self {
+        Self { return_delay: 30000 }
+    }
+}
+
+impl Default for MatchConfig {
+    fn default() -> Self {
+        Self { min_players: 1, warmup: 10, frag_limit: 20, time_limit: 600, intermission: 10 }
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            name: "multiplayer fps".to_string(),
+            password: None,
+            max_hosts: DEFAULT_MAX_HOSTS,
+            port: 0,
+            map: None,
+            rotation: Vec::new(),
+            tick_rate: DEFAULT_TICK_RATE,
+            game_mode: GameMode::Deathmatch,
+            teams: TeamConfig::default(),
+            flags: FlagConfig::default(),
+            weapons: None,
+            skins: None,
+            timeouts: Timeouts::default(),
+            rate_limits: RateLimits::default(),
+            ban_list: None,
+            log_level: LogLevel::Info,
+            admin: None,
+            metrics: MetricsConfig::default(),
+            demo: None,
+            bots: BotConfig::default(),
+            interest: InterestConfig::default(),
+            respawn: RespawnConfig::default(),
+            spawns: SpawnConfig::default(),
+            match_rules: MatchConfig::default(),
+        }
+    }
+}
+
+impl Config {
+    /// Reads a configuration from a JSON file.
+    ///
+    /// # Returns
+    /// - `Err` if the file can't be read or parsed, naming the file and the faulty line.
+    pub fn from_file(path: &str) -> Result<Self, Box<dyn Error>> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("can't read config file \"{}\": {}", path, e))?;
+        let config: Self = serde_json::from_str(&content)
+            .map_err(|e| format!("invalid config file \"{}\": {}", path, e))?;
+        Ok(config)
+    }
+
+    /// Builds the configuration of the server from its command line.
+    ///
+    /// The file given with `--config` is read first, then every flag set on
+    /// the command line replaces the matching value. The result is validated.
+    pub fn load(args: &Args) -> Result<Self, Box<dyn Error>> {
+        let mut config = match &args.config {
+            Some(path) => Self::from_file(path)?,
+            None => Self::default(),
+        };
+        if let Some(name) = &args.name {
+            config.name = name.clone();
+        }
+        if let Some(password) = &args.password {
+            config.password = Some(password.clone());
+        }
+        if let Some(max_hosts) = args.max_hosts {
+            config.max_hosts = max_hosts;
+        }
+        if let Some(port) = args.port {
+            config.port = port;
+        }
+        if let Some(map) = &args.map {
+            config.map = Some(map.clone());
+        }
+        if !args.rotation.is_empty() {
+            config.rotation = args.rotation.clone();
+        }
+        if let Some(tick_rate) = args.tick_rate {
+            config.tick_rate = tick_rate;
+        }
+        if let Some(port) = args.metrics_port {
+            config.metrics.port = Some(port);
+        }
+        if let Some(path) = &args.record {
+            config.demo = Some(path.clone());
+        }
+        if let Some(mode) = args.mode {
+            config.game_mode = mode;
+        }
+        if let Some(strategy) = args.spawns {
+            match config.game_mode {
+                GameMode::Deathmatch => config.spawns.deathmatch = strategy,
+                GameMode::TeamDeathmatch => config.spawns.team_deathmatch = strategy,
+                GameMode::CaptureTheFlag => config.spawns.capture_the_flag = strategy,
+            }
+        }
+        if let Some(count) = args.bots {
+            config.bots.count = count;
+        }
+        if let Some(log_level) = args.log_level {
+            config.log_level = log_level;
+        }
+        match (args.admin_port, &args.admin_password, &mut config.admin) {
+            (Some(port), Some(password), _) => config.admin = Some(AdminConfig { port, password: password.clone() }),
+            (Some(port), None, Some(admin)) => admin.port = port,
+            (None, Some(password), Some(admin)) => admin.password = password.clone(),
+            (Some(_), None, None) => return Err("--admin-port requires --admin-password".into()),
+            _ => (),
+        }
+        if config.map.is_none() {
+            config.map = config.rotation.first().cloned();
+        }
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Checks every value and reports all the problems found at once.
+    pub fn validate(&self) -> Result<(), Box<dyn Error>> {
+        let mut problems = Vec::new();
+        if self.map.is_none() {
+            problems.push("no map given: set \"map\" or \"rotation\" in the config file, or use --map".to_string());
+        }
+        let weapons = self.arsenal();
+        for path in self.map.iter().chain(self.rotation.iter()) {
+            if !Path::new(path).is_file() {
+                problems.push(format!("map file \"{}\" does not exist", path));
+            } else {
+                match Loader::from_file(path) {
+                    Err(e) => problems.push(format!("map file \"{}\" is invalid: {}", path, e)),
+                    Ok(loader) => {
+                        if loader.spawnpoints.is_empty() {
+                            problems.push(format!("map file \"{}\" has no spawn point", path));
+                        }
+                        if self.game_mode == GameMode::CaptureTheFlag {
+                            for team in Team::ALL {
+                                if !loader.flags.iter().any(|f| f.team == team) {
+                                    problems.push(format!("map file \"{}\" has no {} flag base for capture the flag", path, team));
+                                }
+                            }
+                        }
+                        for spawn in &loader.items {
+                            match (&spawn.item, &weapons) {
+                                (ItemKind::Weapon(name), Ok(weapons)) if !weapons.iter().any(|w| w.name == *name) => {
+                                    problems.push(format!("map file \"{}\" places the unknown weapon \"{}\"", path, name));
+                                },
+                                _ => (),
+                            }
+                        }
+                    },
+                }
+            }
+        }
+        match &weapons {
+            Ok(weapons) if weapons.is_empty() => problems.push("the weapons file defines no weapon".to_string()),
+            Ok(weapons) => {
+                problems.extend(weapons.iter().flat_map(Weapon::problems));
+                if !weapons.iter().any(|w| w.starting) {
+                    problems.push("the weapons file defines no starting weapon".to_string());
+                }
+            },
+            Err(e) => problems.push(e.to_string()),
+        }
+        match self.wardrobe() {
+            Ok(skins) if skins.is_empty() => problems.push("the skins file defines no skin".to_string()),
+            Ok(skins) => {
+                for (i, skin) in skins.iter().enumerate() {
+                    if skin.name.is_empty() {
+                        problems.push("a skin has no name".to_string());
+                    } else if skins[..i].iter().any(|s| s.name == skin.name) {
+                        problems.push(format!("skin \"{}\" is defined twice", skin.name));
+                    }
+                }
+            },
+            Err(e) => problems.push(e.to_string()),
+        }
+        if self.max_hosts == 0 {
+            problems.push("max_hosts must be at least 1".to_string());
+        }
+        if self.port > u16::MAX as u32 {
+            problems.push(format!("port must be at most {} (got {})", u16::MAX, self.port));
+        }
+        if self.tick_rate == 0 || self.tick_rate > MAX_TICK_RATE {
+            problems.push(format!("tick_rate must be between 1 and {} (got {})", MAX_TICK_RATE, self.tick_rate));
+        }
+        if self.timeouts.idle == 0 {
+            problems.push("timeouts.idle must be at least 1 second".to_string());
+        }
+        if self.rate_limits.packets_per_second == 0 {
+            problems.push("rate_limits.packets_per_second must be at least 1".to_string());
+        }
+        if let Some(admin) = &self.admin {
+            if admin.password.is_empty() {
+                problems.push("admin.password can't be empty".to_string());
+            }
+        }
+        if self.metrics.summary_interval == Some(0) {
+            problems.push("metrics.summary_interval must be at least 1 second".to_string());
+        }
+        if !(0.0..=180.0).contains(&self.bots.aim_error) {
+            problems.push(format!("bots.aim_error must be between 0 and 180 degrees (got {})", self.bots.aim_error));
+        }
+        if self.bots.view_distance <= 0.0 {
+            problems.push("bots.view_distance must be positive".to_string());
+        }
+        if self.interest.hearing_distance < 0.0 {
+            problems.push("interest.hearing_distance can't be negative".to_string());
+        }
+        if self.match_rules.min_players == 0 {
+            problems.push("match.min_players must be at least 1".to_string());
+        }
+        if let Some(password) = &self.password {
+            if password.is_empty() {
+                problems.push("password can't be empty, remove it to open the server".to_string());
+            }
+        }
+        if problems.is_empty() {
+            return Ok(());
+        }
+        Err(format!("invalid configuration:\n  - {}", problems.join("\n  - ")).into())
+    }
+
+    /// The skins the players can wear, read from `skins`.
+    pub fn wardrobe(&self) -> Result<Vec<Skin>, Box<dyn Error>> {
+        match &self.skins {
+            Some(path) => Skin::from_file(path),
+            None => Ok(vec![Skin::default()]),
+        }
+    }
+
+    /// The weapons the players can hold, read from `weapons`.
+    pub fn arsenal(&self) -> Result<Vec<Weapon>, Box<dyn Error>> {
+        match &self.weapons {
+            Some(path) => Weapon::from_file(path),
+            None => Ok(vec![Weapon { starting: true, ..Weapon::default() }]),
+        }
+    }
+
+    /// Time between two simulation ticks.
+    pub fn tick_duration(&self) -> Duration {
+        Duration::from_secs_f64(1.0 / self.tick_rate as f64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A configuration on the maps, weapons and skins shipped in `conf`.
+    fn shipped() -> Config {
+        Config {
+            map: Some("conf/map1.json".to_string()),
+            weapons: Some("conf/weapons.json".to_string()),
+            skins: Some("conf/skins.json".to_string()),
+            ..Config::default()
+        }
+    }
+
+    /// Writes `content` to a file of the temporary directory and returns its path.
+    fn temp_file(name: &str,content: &str) -> String {
+        let path = std::env::temp_dir().join(format!("multiplayer_fps_{}_{}", std::process::id(), name));
+        fs::write(&path, content).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    fn problems(config: &Config) -> String {
+        config.validate().unwrap_err().to_string()
+    }
+
+    #[test]
+    fn shipped_files_are_valid() {
+        shipped().validate().unwrap();
+        Config::from_file("conf/server.json").unwrap().validate().unwrap();
+    }
+
+    #[test]
+    fn a_map_is_required() {
+        assert!(problems(&Config::default()).contains("no map given"));
+        let config = Config { map: Some("conf/nowhere.json".to_string()), ..shipped() };
+        assert!(problems(&config).contains("map file \"conf/nowhere.json\" does not exist"));
+    }
+
+    #[test]
+    fn every_problem_is_reported_at_once() {
+        let config = Config { max_hosts: 0, tick_rate: 0, password: Some(String::new()), ..shipped() };
+        let problems = problems(&config);
+        assert!(problems.contains("max_hosts must be at least 1"));
+        assert!(problems.contains("tick_rate must be between 1 and 1000 (got 0)"));
+        assert!(problems.contains("password can't be empty"));
+    }
+
+    #[test]
+    fn maps_need_spawn_points() {
+        let mut map: serde_json::Value = serde_json::from_str(&fs::read_to_string("conf/map1.json").unwrap()).unwrap();
+        map["spawnpoints"] = serde_json::json!([]);
+        let path = temp_file("no_spawn.json", &map.to_string());
+        let config = Config { map: Some(path.clone()), ..shipped() };
+        assert!(problems(&config).contains(&format!("map file \"{}\" has no spawn point", path)));
+    }
+
+    #[test]
+    fn maps_only_place_known_weapons() {
+        let config = Config { weapons: None, ..shipped() };
+        assert!(problems(&config).contains("map file \"conf/map1.json\" places the unknown weapon \"shotgun\""));
+    }
+
+    #[test]
+    fn capture_the_flag_needs_both_bases() {
+        let mut map: serde_json::Value = serde_json::from_str(&fs::read_to_string("conf/map1.json").unwrap()).unwrap();
+        map["flags"] = serde_json::json!([]);
+        let path = temp_file("no_flag.json", &map.to_string());
+        let config = Config { map: Some(path.clone()), game_mode: GameMode::CaptureTheFlag, ..shipped() };
+        let problems = problems(&config);
+        assert!(problems.contains(&format!("map file \"{}\" has no red flag base", path)));
+        assert!(problems.contains(&format!("map file \"{}\" has no blue flag base", path)));
+    }
+
+    #[test]
+    fn weapons_need_a_starting_one() {
+        let path = temp_file("no_starting.json", r#"[{"name": "shotgun"}, {"name": "rocket launcher"}]"#);
+        let config = Config { weapons: Some(path), ..shipped() };
+        assert!(problems(&config).contains("the weapons file defines no starting weapon"));
+    }
+}
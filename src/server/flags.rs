@@ -0,0 +1,127 @@
+use std::error::Error;
+
+use crate::{data::{Flag, FlagState, GameEvent, OutputData, Status, Team}, Loader};
+
+use super::config::Config;
+use super::net::Outbox;
+use super::logic::{self, Session};
+
+/// Distance, in tiles, under which a player touches a flag.
+const TOUCH_RADIUS: f32 = 0.7;
+
+/// Every flag of the map of `loader`, on its base.
+pub fn at_bases(loader: &Loader) -> Vec<Flag> {
+    loader.flags.iter()
+        .map(|base| Flag { team: base.team, x: base.x as f32 + 0.5, y: base.y as f32 + 0.5, state: FlagState::AtBase })
+        .collect()
+}
+
+/// Puts `flag` back on its base.
+fn to_base(flag: &mut Flag,loader: &Loader) {
+    if let Some(base) = loader.flags.iter().find(|base| base.team == flag.team) {
+        (flag.x, flag.y) = (base.x as f32 + 0.5, base.y as f32 + 0.5);
+    }
+    flag.state = FlagState::AtBase;
+}
+
+/// The flags as the clients get them. A carried flag is put on its base, the
+/// clients drawing it on its carrier, so they don't learn where a carrier they
+/// can't see stands.
+pub fn public_view(session: &Session) -> Vec<Flag> {
+    let mut flags = session.flags.clone();
+    for flag in flags.iter_mut().filter(|f| f.carrier().is_some()) {
+        if let Some(base) = session.loader.flags.iter().find(|base| base.team == flag.team) {
+            (flag.x, flag.y) = (base.x as f32 + 0.5, base.y as f32 + 0.5);
+        }
+    }
+    flags
+}
+
+/// Sends every client the state of the flags.
+pub fn broadcast(session: &Session,out: &Outbox) -> Result<(),Box<dyn Error>> {
+    logic::broadcast(out, None, &session.players, &OutputData::Flags(public_view(session)))
+}
+
+/// Runs the capture-the-flag rules, once per tick.
+///
+/// - A living player touching the enemy flag takes it.
+/// - A carrier who dies or leaves drops the flag where it stands. It goes
+///   back to its base after `config.flags.return_delay`, or as soon as a
+///   player of its team touches it.
+/// - A carrier touching its own flag, on its base, captures the enemy flag,
+///   scoring a point for its team.
+///
+/// The clients are sent the flags whenever one changes.
+pub fn update(session: &mut Session,out: &Outbox,config: &Config) -> Result<(),Box<dyn Error>> {
+    let now = session.now_ms();
+    let (players, flags, loader) = (&session.players, &mut session.flags, &session.loader);
+    let mut changed = false;
+    for flag in flags.iter_mut() {
+        match flag.state.clone() {
+            FlagState::Carried(nickname) => match players.get_by_nickname(&nickname).map(|i| &players[i]) {
+                Some(carrier) if carrier.status == Status::Alive => (flag.x, flag.y) = (carrier.x, carrier.y),
+                _ => {
+                    flag.state = FlagState::Dropped { until: now + config.flags.return_delay };
+                    changed = true;
+                    info!("{} dropped the {} flag", nickname, flag.team);
+                },
+            },
+            FlagState::Dropped { until } if until <= now => {
+                to_base(flag, loader);
+                changed = true;
+                info!("the {} flag went back to its base", flag.team);
+            },
+            _ => (),
+        }
+    }
+
+    let mut captures: Vec<(String, Team)> = Vec::new();
+    for player in players.iter().filter(|p| p.status == Status::Alive) {
+        let team = match player.team {
+            Some(t) => t,
+            None => continue,
+        };
+        let touches = |flag: &Flag| (flag.x - player.x).hypot(flag.y - player.y) <= TOUCH_RADIUS;
+        let carrying = flags.iter().position(|f| f.carrier() == Some(player.nickname.as_str()));
+        let own_at_base = flags.iter().any(|f| f.team == team && f.state == FlagState::AtBase && touches(f));
+        if let (Some(index), true) = (carrying, own_at_base) {
+            to_base(&mut flags[index], loader);
+            captures.push((player.nickname.clone(), team));
+            changed = true;
+            continue;
+        }
+        for flag in flags.iter_mut().filter(|f| touches(f)) {
+            match flag.state {
+                FlagState::AtBase | FlagState::Dropped { .. } if flag.team != team && carrying.is_none() => {
+                    flag.state = FlagState::Carried(player.nickname.clone());
+                    changed = true;
+                    info!("{} took the {} flag", player.nickname, flag.team);
+                },
+                FlagState::Dropped { .. } if flag.team == team => {
+                    to_base(flag, loader);
+                    changed = true;
+                    info!("{} returned the {} flag", player.nickname, flag.team);
+                },
+                _ => (),
+            }
+        }
+    }
+
+    for (nickname, team) in &captures {
+        if let Some(index) = session.players.get_by_nickname(nickname) {
+            session.players.players[index].stats.captures += 1;
+        }
+        if let Some(scores) = &mut session.team_scores {
+            *scores.entry(*team).or_default() += 1;
+        }
+        info!("{} captured a flag for the {} team", nickname, team);
+        logic::announce(&session.players, out, GameEvent::Capture { nickname: nickname.clone(), team: *team })?;
+    }
+    if changed {
+        broadcast(session, out)?;
+    }
+    if !captures.is_empty() {
+        logic::broadcast_scores(session, out)?;
+    }
+    Ok(())
+}
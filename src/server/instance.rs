@@ -1,10 +1,16 @@
-use std::net::{Ipv4Addr, UdpSocket};
-use std::thread;
+use std::net::{Ipv4Addr, SocketAddr, UdpSocket};
+use std::path::Path;
+use std::sync::{atomic::{AtomicBool, Ordering}, Arc};
+use std::thread::{self, JoinHandle};
 
-use crate::data::{InputData, Players};
-use crate::server::logic::{connection, update};
+use super::config::Config;
+use super::logic::running;
 
 const DEFAULT_MAX_HOSTS: u8 = 4;
+/// Weapons file used when it exists, the players only getting the default rifle otherwise.
+const DEFAULT_WEAPONS: &str = "conf/weapons.json";
+/// Skins file used when it exists, everyone being a goblin otherwise.
+const DEFAULT_SKINS: &str = "conf/skins.json";
 
 type Error = Box<dyn std::error::Error>;
 
@@ -14,19 +20,21 @@ type Error = Box<dyn std::error::Error>;
 /// All modification made after will be applied on restart
 ///
 /// # Fields
-/// - `port`: The network port on which the server instance listens.
+/// - `port`: The network port on which the server instance listens, random if 0.
 /// - `frequency`: The tick/update frequency of the server instance.
 /// - `max_hosts`: The maximum number of hosts allowed to connect (default is 4).
+/// - `map`: The map file played.
 pub struct Instance {
     port: u32,
-    _frequency: u32,
+    frequency: u32,
     max_hosts: u8, // 4 by default can be changed
+    map: String,
 }
 
 impl Instance {
     /// Create a new server instance
-    pub fn new(port: u32, _frequency: u32) -> Self {
-        Self { port, _frequency,max_hosts:DEFAULT_MAX_HOSTS, }
+    pub fn new(port: u32, frequency: u32, map: String) -> Self {
+        Self { port, frequency, max_hosts:DEFAULT_MAX_HOSTS, map }
     }
 
     /// Set the max number of hosts
@@ -35,16 +43,58 @@ impl Instance {
         self.max_hosts = value;
     }
 
-    // pub fn run(&self) -> std::io::Result<()> {
-    //     let socket = UdpSocket::bind(format!("{}:{}",Ipv4Addr::new(0, 0, 0, 0),self.port))?;
-    //     socket.set_nonblocking(true)?;
-    //     let instance = self.clone();
-    //     thread::spawn(move || {
-    //         match running(socket, &instance) {
-    //             Ok(_) => println!("server stopped succesfully"),
-    //             Err(e) => println!("SERVER ERROR : {}",e.as_ref())
-    //         };
-    //     });
-    //     Ok(())
-    // }
+    /// Starts the server on its own thread, listening on every interface.
+    ///
+    /// # Returns
+    /// * `Ok(RunningInstance)` - The handle stopping the server once dropped.
+    /// * `Err` if the configuration is invalid or the port can't be bound.
+    pub fn run(&self) -> Result<RunningInstance,Error> {
+        let config = Config {
+            port: self.port,
+            tick_rate: self.frequency,
+            max_hosts: self.max_hosts,
+            map: Some(self.map.clone()),
+            weapons: Path::new(DEFAULT_WEAPONS).is_file().then(|| DEFAULT_WEAPONS.to_string()),
+            skins: Path::new(DEFAULT_SKINS).is_file().then(|| DEFAULT_SKINS.to_string()),
+            ..Config::default()
+        };
+        config.validate()?;
+        let socket = UdpSocket::bind(format!("{}:{}",Ipv4Addr::new(0, 0, 0, 0),self.port))?;
+        let addr = socket.local_addr()?;
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let shutdown_clone = shutdown.clone();
+        let thread = thread::spawn(move || {
+            match running(socket, &config, None, &shutdown_clone) {
+                Ok(_) => println!("server stopped succesfully"),
+                Err(e) => println!("SERVER ERROR : {}",e.as_ref())
+            };
+        });
+        Ok(RunningInstance { addr, shutdown, thread: Some(thread) })
+    }
+}
+
+/// A server started by `Instance::run`, stopped when dropped.
+///
+/// # Fields
+/// - `addr`: The address the server is bound to.
+pub struct RunningInstance {
+    pub addr: SocketAddr,
+    shutdown: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl RunningInstance {
+    /// Disconnects every player and waits for the server thread to end.
+    pub fn stop(&mut self) {
+        self.shutdown.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for RunningInstance {
+    fn drop(&mut self) {
+        self.stop();
+    }
 }
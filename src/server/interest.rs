@@ -0,0 +1,94 @@
+use std::{collections::{HashMap, HashSet}, error::Error};
+
+use crate::{data::OutputData, entities::{Player, Players}, world::Map};
+
+use super::config::InterestConfig;
+use super::net::Outbox;
+use super::logic::{self, Session};
+
+/// Players every client has been told about, indexed by the viewer's nickname.
+///
+/// A client only receives the state of the players it could plausibly see,
+/// with a clear line of sight on the map, or hear, within `hearing_distance`
+/// tiles even through walls. The others are hidden from it until they get
+/// relevant again, so a modified client can't draw them.
+///
+/// # Fields
+/// - `enabled`: Whether the filtering is on. Every player is relevant if not.
+/// - `hearing_distance`: Tiles under which a player is relevant through walls.
+/// - `known`: The players each viewer currently holds.
+pub struct Interest {
+    pub enabled: bool,
+    pub hearing_distance: f32,
+    known: HashMap<String, HashSet<String>>,
+}
+
+impl Interest {
+    pub fn new(config: &InterestConfig) -> Self {
+        Self { enabled: config.enabled, hearing_distance: config.hearing_distance, known: HashMap::new() }
+    }
+
+    /// Whether `subject` could be seen or heard by `viewer`.
+    pub fn relevant(&self, map: &Map, viewer: &Player, subject: &Player) -> bool {
+        if !self.enabled {
+            return true;
+        }
+        let dist = (subject.x - viewer.x).hypot(subject.y - viewer.y);
+        dist <= self.hearing_distance || map.line_of_sight((viewer.x, viewer.y), (subject.x, subject.y))
+    }
+
+    /// Whether the client of `viewer` holds the state of `subject`.
+    pub fn knows(&self, viewer: &str, subject: &str) -> bool {
+        self.known.get(viewer).is_some_and(|known| known.contains(subject))
+    }
+
+    /// Removes `nickname` from every client, so a new player using it is sent whole.
+    pub fn forget(&mut self, nickname: &str) {
+        self.known.remove(nickname);
+        for known in self.known.values_mut() {
+            known.remove(nickname);
+        }
+    }
+
+    /// The players `viewer` gets in a full state message, such as `Connecting`
+    /// or `MapChange`, which replaces everything its client knew. Each one is
+    /// reduced to its `Player::public_view`.
+    pub fn visible_to(&mut self, map: &Map, viewer: &Player, players: &Players) -> Players {
+        let mut visible = Players::new();
+        for subject in players.iter() {
+            if subject.nickname != viewer.nickname && self.relevant(map, viewer, subject) {
+                visible.push(subject.public_view());
+            }
+        }
+        self.known.insert(viewer.nickname.clone(), visible.iter().map(|p| p.nickname.clone()).collect());
+        visible
+    }
+}
+
+/// Sends every client the players entering and leaving its view since the last tick.
+///
+/// A player getting relevant is sent with `New`, as its `Player::public_view`, one leaving the view
+/// is removed from the client with `Hide`.
+pub fn refresh(session: &mut Session, out: &Outbox) -> Result<(), Box<dyn Error>> {
+    let interest = &mut session.interest;
+    interest.known.retain(|viewer, _| session.players.get_by_nickname(viewer).is_some_and(|i| !session.players[i].bot));
+    for viewer in session.players.iter().filter(|p| !p.bot) {
+        let relevant: HashSet<String> = session.players.iter()
+            .filter(|subject| subject.nickname != viewer.nickname && interest.relevant(&session.map, viewer, subject))
+            .map(|subject| subject.nickname.clone())
+            .collect();
+        let known = interest.known.entry(viewer.nickname.clone()).or_default();
+        for nickname in relevant.difference(known) {
+            if let Some(index) = session.players.get_by_nickname(nickname) {
+                logic::send(out, viewer.addr, &OutputData::New(session.players[index].public_view()))?;
+            }
+        }
+        for nickname in known.difference(&relevant) {
+            if session.players.get_by_nickname(nickname).is_some() {
+                logic::send(out, viewer.addr, &OutputData::Hide(nickname.clone()))?;
+            }
+        }
+        *known = relevant;
+    }
+    Ok(())
+}
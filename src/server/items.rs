@@ -0,0 +1,92 @@
+use std::error::Error;
+
+use crate::{data::{Item, ItemKind, OutputData, Status}, entities::{Player, Weapon, MAX_ARMOR, MAX_HEALTH}, Loader};
+
+use super::net::Outbox;
+use super::logic::{self, Session};
+use super::weapons;
+
+/// Distance, in tiles, under which a player picks up an item.
+const TOUCH_RADIUS: f32 = 0.6;
+
+/// Every item of the map of `loader`, ready to be picked up.
+pub fn on_map(loader: &Loader) -> Vec<Item> {
+    loader.items.iter()
+        .map(|spawn| Item { kind: spawn.item.clone(), x: spawn.x as f32 + 0.5, y: spawn.y as f32 + 0.5, texture: spawn.texture.clone(), back_at: None })
+        .collect()
+}
+
+/// Gives `item` to `player`. Ammo refills the magazines without shortening
+/// the wait before the next shot.
+///
+/// # Returns
+/// * `false` if the player has no use for it, the item staying on the ground.
+fn apply(player: &mut Player,item: &ItemKind,weapons: &[Weapon]) -> bool {
+    let loadout = &mut player.loadout;
+    match item {
+        ItemKind::Health(amount) if player.health < MAX_HEALTH => player.health = player.health.saturating_add(*amount).min(MAX_HEALTH),
+        ItemKind::Armor(amount) if player.armor < MAX_ARMOR => player.armor = player.armor.saturating_add(*amount).min(MAX_ARMOR),
+        ItemKind::Ammo if weapons.iter().zip(&loadout.ammo).any(|(w, ammo)| ammo.is_some_and(|a| a < w.ammo)) => {
+            for (ammo, weapon) in loadout.ammo.iter_mut().zip(weapons).filter(|(ammo, _)| ammo.is_some()) {
+                *ammo = Some(weapon.ammo);
+            }
+        },
+        ItemKind::Weapon(name) => {
+            let index = match weapons.iter().position(|w| w.name == *name) {
+                Some(i) => i,
+                None => return false,
+            };
+            if loadout.ammo.get(index) == Some(&Some(weapons[index].ammo)) {
+                return false;
+            }
+            loadout.give(index, weapons[index].ammo);
+        },
+        _ => return false,
+    }
+    true
+}
+
+/// Hands out the items, once per tick.
+///
+/// A living player walking over an item it has a use for picks it up. The
+/// item comes back after the respawn time set by the map. Every client is
+/// told when an item is taken or comes back, and the player picking it up
+/// receives its new health, armor and loadout.
+pub fn update(session: &mut Session,out: &Outbox) -> Result<(),Box<dyn Error>> {
+    let now = session.now_ms();
+    for index in 0..session.items.len() {
+        let item = &mut session.items[index];
+        match item.back_at {
+            Some(time) if time <= now => item.back_at = None,
+            Some(_) => continue,
+            None => {
+                let mut taker = None;
+                for player in session.players.players.iter_mut().filter(|p| p.status == Status::Alive && (p.x - item.x).hypot(p.y - item.y) <= TOUCH_RADIUS) {
+                    if apply(player, &item.kind, &session.weapons) {
+                        taker = Some(player.nickname.clone());
+                        break;
+                    }
+                }
+                let nickname = match taker {
+                    Some(n) => n,
+                    None => continue,
+                };
+                item.back_at = Some(now + session.loader.items.get(index).map_or(0, |spawn| spawn.respawn));
+                debug!("{} picked up {:?}", nickname, item.kind);
+                if let Some(p) = session.players.get_by_nickname(&nickname) {
+                    let player = &session.players[p];
+                    logic::send_to_player(out, player, &OutputData::Vitals { health: player.health, armor: player.armor })?;
+                    weapons::send(session, p, out)?;
+                }
+            },
+        }
+        let msg = OutputData::ItemState { index, back_at: session.items[index].back_at };
+        logic::broadcast(out, None, &session.players, &msg)?;
+    }
+    Ok(())
+}
+
+/// Sends every client all the items.
+pub fn broadcast(session: &Session,out: &Outbox) -> Result<(),Box<dyn Error>> {
+    logic::broadcast(out, None, &session.players, &OutputData::Items(session.items.clone()))
+}
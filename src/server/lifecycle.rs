@@ -0,0 +1,229 @@
+use std::error::Error;
+
+use crate::data::{OutputData, Phase, Stats};
+
+use super::config::Config;
+use super::flags;
+use super::items;
+use super::net::Outbox;
+use super::logic::{self, Session};
+
+/// Moves the match to its next phase once its condition is met.
+///
+/// - `Warmup`: counts down once `min_players` humans are connected, then
+///   resets the scores, respawns everyone and starts the match.
+/// - `InProgress`: ends on the frag limit or the time limit, naming the best
+///   player, or team in the team modes, the winner. Falls back to warmup if
+///   everybody left.
+/// - `Intermission`: switches to the next map of the rotation and warms up,
+///   on the current map if the next one can't be loaded.
+///
+/// Every change is broadcast with `OutputData::Phase`.
+pub fn advance(session: &mut Session,out: &Outbox,config: &Config) -> Result<(),Box<dyn Error>> {
+    let rules = &config.match_rules;
+    let now = session.now_ms();
+    let enough = session.players.iter().filter(|p| !p.bot).count() >= rules.min_players as usize;
+    let next = match &session.phase {
+        Phase::Warmup { until: None } if enough => Phase::Warmup { until: Some(now + rules.warmup * 1000) },
+        Phase::Warmup { until: Some(_) } if !enough => Phase::Warmup { until: None },
+        Phase::Warmup { until: Some(time) } if *time <= now => start(session, out, config)?,
+        Phase::InProgress { .. } if session.players.is_empty() => Phase::Warmup { until: None },
+        Phase::InProgress { until, frag_limit } => {
+            let best = match &session.team_scores {
+                Some(scores) => scores.values().copied().max(),
+                None => session.players.iter().map(|p| p.stats.score()).max(),
+            }.unwrap_or(0);
+            let fragged = frag_limit.is_some_and(|limit| best >= limit as i32);
+            let timed_out = until.is_some_and(|time| time <= now);
+            if !fragged && !timed_out {
+                return Ok(());
+            }
+            let winner = winner(session);
+            match &winner {
+                Some(nickname) => info!("match over, {} wins", nickname),
+                None => info!("match over, draw"),
+            }
+            Phase::Intermission { winner, until: now + rules.intermission * 1000 }
+        },
+        Phase::Intermission { until, .. } if *until <= now => {
+            reset_scores(session);
+            // Retrying on every tick would fail the same way: the current map is played again
+            if let Err(e) = logic::next_map(session, out) {
+                error!("can't load the next map, staying on {}: {}", session.map_path, e);
+            }
+            Phase::Warmup { until: None }
+        },
+        _ => return Ok(()),
+    };
+    session.phase = next;
+    logic::broadcast(out, None, &session.players, &OutputData::Phase(session.phase.clone()))?;
+    logic::broadcast_scores(session, out)
+}
+
+/// Resets the scores, the flags and the items and brings every player back
+/// to a spawn point with full health.
+fn start(session: &mut Session,out: &Outbox,config: &Config) -> Result<Phase,Box<dyn Error>> {
+    let rules = &config.match_rules;
+    reset_scores(session);
+    if !session.flags.is_empty() {
+        session.flags = flags::at_bases(&session.loader);
+        flags::broadcast(session, out)?;
+    }
+    if !session.items.is_empty() {
+        session.items = items::on_map(&session.loader);
+        items::broadcast(session, out)?;
+    }
+    for index in 0..session.players.len() {
+        logic::respawn(session, index, out)?;
+    }
+    info!("match started on {}", session.map_path);
+    Ok(Phase::InProgress {
+        until: (rules.time_limit > 0).then(|| session.now_ms() + rules.time_limit * 1000),
+        frag_limit: (rules.frag_limit > 0).then_some(rules.frag_limit),
+    })
+}
+
+fn reset_scores(session: &mut Session) {
+    for player in session.players.players.iter_mut() {
+        player.stats = Stats::default();
+    }
+    for score in session.team_scores.iter_mut().flat_map(|scores| scores.values_mut()) {
+        *score = 0;
+    }
+}
+
+/// The best player, or team in the team modes, `None` on a draw or without players.
+fn winner(session: &Session) -> Option<String> {
+    let standings = logic::standings(session);
+    if session.team_scores.is_some() {
+        return match standings.teams.as_slice() {
+            [first, second, ..] if first.1 == second.1 => None,
+            [first, ..] => Some(format!("{} team", first.0)),
+            [] => None,
+        };
+    }
+    match standings.players.as_slice() {
+        [first, second, ..] if first.stats.score() == second.stats.score() => None,
+        [first, ..] => Some(first.nickname.clone()),
+        [] => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::mpsc::{channel, Receiver}, time::Duration};
+
+    use crate::entities::Player;
+    use super::*;
+    use super::super::{clock::Clock, net::Outgoing};
+
+    struct Match {
+        session: Session,
+        config: Config,
+        out: Outbox,
+        _outgoing: Receiver<Outgoing>,
+    }
+
+    impl Match {
+        fn new() -> Self {
+            let config = Config { map: Some("conf/map1.json".to_string()), ..Config::default() };
+            let (tx, outgoing) = channel();
+            let session = Session::new(&config, Clock::manual()).unwrap();
+            Self { session, config, out: Outbox::new(tx), _outgoing: outgoing }
+        }
+
+        fn join(&mut self,nickname: &str,bot: bool) {
+            let mut player = Player::new(nickname.to_string(), (1.5, 1.5, 0.0), "goblin");
+            player.addr = format!("127.0.0.1:{}", 4000 + self.session.players.len()).parse().unwrap();
+            player.bot = bot;
+            self.session.players.push(player);
+        }
+
+        /// Moves the clock forward by `seconds`, then advances the match.
+        fn advance(&mut self,seconds: u64) -> &Phase {
+            self.session.clock.advance(Duration::from_secs(seconds));
+            advance(&mut self.session, &self.out, &self.config).unwrap();
+            &self.session.phase
+        }
+
+        /// Skips the warmup with one human player.
+        fn started() -> Self {
+            let mut game = Self::new();
+            game.join("alice", false);
+            game.advance(0);
+            let warmup = game.config.match_rules.warmup;
+            game.advance(warmup);
+            game
+        }
+    }
+
+    #[test]
+    fn bots_alone_dont_start_the_countdown() {
+        let mut game = Match::new();
+        game.join("bot", true);
+        assert_eq!(game.advance(0), &Phase::Warmup { until: None });
+        game.join("alice", false);
+        let until = 1000 + game.config.match_rules.warmup * 1000;
+        assert_eq!(game.advance(1), &Phase::Warmup { until: Some(until) });
+    }
+
+    #[test]
+    fn countdown_stops_when_players_leave() {
+        let mut game = Match::new();
+        game.join("alice", false);
+        game.advance(0);
+        game.session.players.remove(0);
+        assert_eq!(game.advance(1), &Phase::Warmup { until: None });
+    }
+
+    #[test]
+    fn match_starts_with_reset_scores() {
+        let mut game = Match::new();
+        game.join("alice", false);
+        game.advance(0);
+        game.session.players.players[0].stats.kill();
+        let warmup = game.config.match_rules.warmup;
+        assert!(matches!(game.advance(warmup - 1), Phase::Warmup { .. }));
+        let rules = &game.config.match_rules;
+        let expected = Phase::InProgress { until: Some((warmup + rules.time_limit) * 1000), frag_limit: Some(rules.frag_limit) };
+        assert_eq!(game.advance(1), &expected);
+        assert_eq!(game.session.players[0].stats, Stats::default());
+    }
+
+    #[test]
+    fn frag_limit_ends_the_match_with_a_winner() {
+        let mut game = Match::started();
+        game.join("bob", false);
+        for _ in 0..game.config.match_rules.frag_limit {
+            game.session.players.players[1].stats.kill();
+        }
+        assert!(matches!(game.advance(0), Phase::Intermission { winner: Some(w), .. } if w == "bob"));
+    }
+
+    #[test]
+    fn time_limit_ends_the_match_on_a_draw() {
+        let mut game = Match::started();
+        game.join("bob", false);
+        let time_limit = game.config.match_rules.time_limit;
+        assert!(matches!(game.advance(time_limit - 1), Phase::InProgress { .. }));
+        assert!(matches!(game.advance(1), Phase::Intermission { winner: None, .. }));
+    }
+
+    #[test]
+    fn match_stops_when_everybody_left() {
+        let mut game = Match::started();
+        game.session.players.remove(0);
+        assert_eq!(game.advance(0), &Phase::Warmup { until: None });
+    }
+
+    #[test]
+    fn intermission_warms_up_on_the_current_map_when_the_next_one_is_missing() {
+        let mut game = Match::started();
+        game.session.rotation = vec!["conf/missing.json".to_string()];
+        let time_limit = game.config.match_rules.time_limit;
+        game.advance(time_limit);
+        let intermission = game.config.match_rules.intermission;
+        assert_eq!(game.advance(intermission), &Phase::Warmup { until: None });
+        assert_eq!(game.session.map_path, "conf/map1.json");
+    }
+}
@@ -0,0 +1,37 @@
+use std::{collections::HashMap, net::SocketAddr};
+
+/// Length of a window, in milliseconds.
+const WINDOW: u64 = 1000;
+
+/// Counts the packets received from each address over one second windows,
+/// timed in server time.
+pub struct RateLimiter {
+    max_per_second: u32,
+    windows: HashMap<SocketAddr, (u64, u32)>,
+}
+
+impl RateLimiter {
+    pub fn new(max_per_second: u32) -> Self {
+        Self { max_per_second, windows: HashMap::new() }
+    }
+
+    /// Records a packet from `addr`, received at the server time `now`.
+    ///
+    /// # Returns
+    /// * `false` if `addr` went over its budget for the current window and the
+    ///   packet must be dropped.
+    pub fn allow(&mut self, addr: SocketAddr, now: u64) -> bool {
+        let (start, count) = self.windows.entry(addr).or_insert((now, 0));
+        if now.saturating_sub(*start) >= WINDOW {
+            *start = now;
+            *count = 0;
+        }
+        *count += 1;
+        *count <= self.max_per_second
+    }
+
+    /// Forgets the addresses that sent nothing during the last window.
+    pub fn prune(&mut self, now: u64) {
+        self.windows.retain(|_, (start, _)| now.saturating_sub(*start) < WINDOW);
+    }
+}
@@ -0,0 +1,57 @@
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+/// Verbosity of the server output, from the least to the most verbose.
+pub enum LogLevel {
+    Error,
+    Warn,
+    Info,
+    Debug,
+}
+
+static LEVEL: AtomicU8 = AtomicU8::new(LogLevel::Info as u8);
+
+/// Sets the most verbose level that will be printed.
+pub fn set_level(level: LogLevel) {
+    LEVEL.store(level as u8, Ordering::Relaxed);
+}
+
+/// Tells if messages of `level` are printed.
+pub fn enabled(level: LogLevel) -> bool {
+    level as u8 <= LEVEL.load(Ordering::Relaxed)
+}
+
+/// Prints to stderr if the `Error` level is enabled.
+#[macro_export]
+macro_rules! error {
+    ($($arg:tt)*) => {
+        if $crate::server::log::enabled($crate::server::log::LogLevel::Error) { eprintln!($($arg)*) }
+    };
+}
+
+/// Prints to stderr if the `Warn` level is enabled.
+#[macro_export]
+macro_rules! warn {
+    ($($arg:tt)*) => {
+        if $crate::server::log::enabled($crate::server::log::LogLevel::Warn) { eprintln!($($arg)*) }
+    };
+}
+
+/// Prints to stdout if the `Info` level is enabled.
+#[macro_export]
+macro_rules! info {
+    ($($arg:tt)*) => {
+        if $crate::server::log::enabled($crate::server::log::LogLevel::Info) { println!($($arg)*) }
+    };
+}
+
+/// Prints to stdout if the `Debug` level is enabled.
+#[macro_export]
+macro_rules! debug {
+    ($($arg:tt)*) => {
+        if $crate::server::log::enabled($crate::server::log::LogLevel::Debug) { println!($($arg)*) }
+    };
+}
@@ -1,10 +1,149 @@
-use std::{error::Error, net::{SocketAddr, UdpSocket}};
+use std::{collections::{BTreeMap, HashMap}, error::Error, net::SocketAddr, thread, time::{Duration, Instant}};
+use std::sync::{atomic::{AtomicBool, Ordering}, mpsc::{channel, RecvTimeoutError}, Arc, Mutex};
 
-use multiplayer_fps::{data::{Connection, Deny, InputData, OutputData, Status, Update}, entities::{Player, Players}, world::Map};
-use multiplayer_fps::Loader;
-use rand::prelude::*;
+use crate::{data::{Connection, Deny, Flag, GameEvent, Hit, InputData, Item, Loadout, OutputData, Phase, Score, Standings, Status, Team, Update}, entities::{Player, Players, Skin, Weapon, HIT_RADIUS, MAX_HEALTH}, world::Map};
+use crate::{demo::DemoWriter, transport::Transport, Loader};
 
-use crate::args::Args;
+use super::admin::{AdminCommand, AdminQueue, AdminResponse, PlayerStatus, ServerStatus};
+use super::bans::BanList;
+use super::bots::{self, Bots};
+use super::clock::Clock;
+use super::config::{Config, GameMode};
+use super::flags;
+use super::interest::{self, Interest};
+use super::items;
+use super::lifecycle;
+use super::limits::RateLimiter;
+use super::metrics;
+use super::net::{self, Inbox, Outbox, Recorder};
+use super::projectiles::{self, Projectiles};
+use super::spawns::Spawns;
+use super::teams;
+use super::weapons;
+use rand::Rng;
+
+/// Milliseconds between two round trip measurements.
+const PING_INTERVAL: u64 = 1000;
+
+/// Game state owned by the simulation loop.
+///
+/// # Fields
+/// - `players`: The connected players.
+/// - `loader`: The map configuration sent to connecting clients.
+/// - `map`: The map built from `loader`, used for collisions and spawns.
+/// - `map_path`: The file `loader` was read from.
+/// - `rotation`: The map files played one after the other.
+/// - `rotation_index`: Position of the next map in `rotation`.
+/// - `bans`: Addresses refused on connection.
+/// - `limiter`: Packet budget of every address.
+/// - `last_seen`: Server time each connected address sent its last packet at.
+/// - `last_ping`: Server time the players were last pinged at.
+/// - `last_summary`: Server time the last metrics summary was logged at.
+/// - `demo`: The demo file the match is recorded to, if any, shared with the `Outbox` writing the broadcasts to it.
+/// - `bots`: The state of the server-side players.
+/// - `interest`: The players each client has been sent.
+/// - `phase`: Where the match stands in its lifecycle.
+/// - `team_scores`: Score of each team, in the team modes only.
+/// - `flags`: The flags of the capture-the-flag mode, empty in the other modes.
+/// - `items`: The items of the map.
+/// - `weapons`: The weapons the players can hold.
+/// - `skins`: The skins the players can wear, the default one first.
+/// - `projectiles`: The projectiles in flight.
+/// - `spawns`: The spawn point choice and when each spawn point was last used.
+/// - `clock`: The server time, following the real time or moved by hand.
+pub struct Session {
+    pub players: Players,
+    pub loader: Loader,
+    pub map: Map,
+    pub map_path: String,
+    pub rotation: Vec<String>,
+    rotation_index: usize,
+    pub bans: BanList,
+    pub limiter: RateLimiter,
+    pub last_seen: HashMap<SocketAddr, u64>,
+    pub last_ping: u64,
+    pub last_summary: u64,
+    pub demo: Option<Recorder>,
+    pub bots: Bots,
+    pub interest: Interest,
+    pub phase: Phase,
+    pub team_scores: Option<BTreeMap<Team, i32>>,
+    pub flags: Vec<Flag>,
+    pub items: Vec<Item>,
+    pub weapons: Vec<Weapon>,
+    pub skins: Vec<Skin>,
+    pub projectiles: Projectiles,
+    pub spawns: Spawns,
+    pub clock: Clock,
+}
+
+impl Session {
+    /// Creates an empty session on the first map of the configuration, timed with `clock`.
+    ///
+    /// An empty rotation keeps playing that map. Otherwise the rotation
+    /// resumes after it if it is part of it, or from its start.
+    pub fn new(config: &Config,clock: Clock) -> Result<Self, Box<dyn Error>> {
+        let map_path = match &config.map {
+            Some(m) => m.clone(),
+            None => return Err("no map to start the session on".into()),
+        };
+        let loader = Loader::from_file(&map_path)?;
+        let map = Map::from(&loader);
+        let rotation = if config.rotation.is_empty() { vec![map_path.clone()] } else { config.rotation.clone() };
+        let rotation_index = rotation.iter().position(|m| *m == map_path).map_or(0, |i| (i + 1) % rotation.len());
+        let players = Players::new();
+        let flags = if config.game_mode == GameMode::CaptureTheFlag { flags::at_bases(&loader) } else { Vec::new() };
+        let items = items::on_map(&loader);
+        let skins = config.wardrobe()?;
+        let demo = match &config.demo {
+            Some(path) => {
+                info!("recording the match to {}", path);
+                let mut demo = DemoWriter::create(path, 0, &loader, &players)?;
+                // The replay needs the skin textures whatever the map
+                demo.record(0, &OutputData::Skins(skins.clone()))?;
+                Some(Arc::new(Mutex::new(demo)))
+            },
+            None => None,
+        };
+        Ok(Self {
+            players,
+            loader,
+            map,
+            map_path,
+            rotation,
+            rotation_index,
+            bans: BanList::new(config.ban_list.clone())?,
+            limiter: RateLimiter::new(config.rate_limits.packets_per_second),
+            last_seen: HashMap::new(),
+            last_ping: clock.now_ms(),
+            last_summary: clock.now_ms(),
+            demo,
+            bots: Bots::new(),
+            interest: Interest::new(&config.interest),
+            phase: Phase::default(),
+            team_scores: config.game_mode.teams().then(|| Team::ALL.iter().map(|t| (*t, 0)).collect()),
+            flags,
+            items,
+            weapons: config.arsenal()?,
+            skins,
+            projectiles: Projectiles::new(),
+            spawns: Spawns::new(&config.spawns, config.spawns.strategy(config.game_mode)),
+            clock,
+        })
+    }
+
+    /// Milliseconds elapsed since the server started, the server time of the protocol.
+    pub fn now_ms(&self) -> u64 {
+        self.clock.now_ms()
+    }
+
+    /// Returns the next map of the rotation and moves the rotation forward.
+    pub fn next_in_rotation(&mut self) -> String {
+        let path = self.rotation[self.rotation_index].clone();
+        self.rotation_index = (self.rotation_index + 1) % self.rotation.len();
+        path
+    }
+}
 
 
 // use data::{Connection, Deny, Host, Players, OutputData, Update};
@@ -12,101 +151,186 @@ use crate::args::Args;
 /// Broadcasts a message to a list of socket addresses via UDP.
 ///
 /// # Arguments
-/// * `socket` - The UDP socket to use for sending messages.
+/// * `out` - The queue of the sending thread.
 /// * `from` - An optional address to exclude from the broadcast (e.g., the sender).
 /// * `Players` - A list of socket addresses to which the message should be sent.
-/// * `data` - The message (as a string) to be broadcast.
+/// * `msg` - The message to be broadcast, serialized once by the sending thread.
+///
+/// Bots have no client and are skipped.
 ///
 /// # Returns
-/// * `std::io::Result<()>` - Returns Ok if all messages are sent successfully, or an error otherwise.
+/// * `Ok(())` if the message is queued, or an error if the sending thread stopped.
 pub fn broadcast(
-    socket: &UdpSocket,
+    out: &Outbox,
     from: Option<SocketAddr>,
     players: &Players,
-    data: String,
-) -> std::io::Result<()> {
-    if players.len() <= 1 {
-        return Ok(());
-    }
+    msg: &OutputData,
+) -> Result<(),Box<dyn Error>> {
+    broadcast_if(out, from, players, msg, |_| true)
+}
+
+/// Same as `broadcast`, restricted to the players `filter` accepts.
+///
+/// The message is recorded to the demo whoever it is sent to.
+pub fn broadcast_if<F: Fn(&Player) -> bool>(
+    out: &Outbox,
+    from: Option<SocketAddr>,
+    players: &Players,
+    msg: &OutputData,
+    filter: F,
+) -> Result<(),Box<dyn Error>> {
+    out.record(msg)?;
+    let mut to = Vec::new();
     for addr in players.iter() {
+        if addr.bot || !filter(addr) {
+            continue;
+        }
         match from {
             Some(current_host) => if current_host == addr.addr { continue; },
             None => {},
         }
-        socket.send_to(data.as_bytes(), addr.addr)?;
+        to.push(addr.addr);
     }
-    Ok(())
+    out.send(to, msg.clone())
+}
+
+/// Queues a message for a single address.
+pub fn send(out: &Outbox, addr: SocketAddr, msg: &OutputData) -> Result<(),Box<dyn Error>> {
+    out.send(vec![addr], msg.clone())
+}
+
+/// Sends a message to the client of `player`, or does nothing for a bot.
+pub fn send_to_player(out: &Outbox, player: &Player, msg: &OutputData) -> Result<(),Box<dyn Error>> {
+    if player.bot {
+        return Ok(());
+    }
+    send(out, player.addr, msg)
 }
 
 /// Handles a new connection attempt from a client.
 ///
 /// This function performs several validation checks:
+/// - If the address is banned, it sends a denial message.
+/// - If the server asks for a password and it doesn't match, it sends a denial message.
 /// - If the nickname is already used, it sends a denial message.
 /// - If the address is already used, it sends a denial message.
-/// - If the server is full (based on `max_hosts`), it sends a denial message.
+/// - If the server is full of humans (based on `max_hosts`), it sends a denial message.
 /// If all checks pass:
-/// - A new `Host` is initialized and added to the list.
-/// - A broadcast message is sent to all clients with the new host's data.
+/// - A bot leaves if it holds the last slot.
+/// - A new `Host` is initialized and added to the list, in the team it asked
+///   for if that keeps the teams balanced in the team modes.
+/// - The new host receives the players it can see, the weapons, the items,
+///   the phase of the match and the flags in capture-the-flag.
+///   The others learn about it on the next tick, once it enters their view.
 ///
 /// # Arguments
-/// * `Players` - The current list of connected Players (mutable reference).
+/// * `session` - The session to add the player to.
 /// * `data` - The connection data received from the client.
-/// * `socket` - The UDP socket used for communication.
-/// * `max_hosts` - The maximum number of allowed Players.
+/// * `out` - The queue of the sending thread.
+/// * `config` - The server configuration (password, maximum number of players).
 ///
 /// # Returns
 /// * `Ok(())` on success.
-/// * `Err(Box<dyn Error>)` if any error occurs during processing (e.g., serialization or socket errors).
-pub fn connection(players: &mut Players,data: Connection,socket: &UdpSocket,max_hosts: u8,loader: Loader) -> Result<(),Box<dyn Error>>{
+/// * `Err(Box<dyn Error>)` if any error occurs during processing (e.g., no spawnpoint or a stopped sending thread).
+pub fn connection(session: &mut Session,data: Connection,out: &Outbox,config: &Config) -> Result<(),Box<dyn Error>>{
+    let players = &mut session.players;
+    let max_hosts = config.max_hosts;
+    if session.bans.contains(&data.addr.ip()) {
+        let msg = OutputData::AccessDeny(Deny {reason: "you are banned from this server".to_string()});
+        return send(out, data.addr, &msg);
+    }
+    if config.password.is_some() && data.password != config.password {
+        let msg = OutputData::AccessDeny(Deny {reason: "wrong password".to_string()});
+        return send(out, data.addr, &msg);
+    }
     if players.get_by_nickname(&data.nickname).is_some() {
         let msg = OutputData::AccessDeny(Deny {reason: format!("the nickname \"{}\" is already used",data.nickname)});
-        let serialized = serde_json::to_string(&msg)?;
-        socket.send_to(serialized.as_bytes(),data.addr)?;
+        return send(out, data.addr, &msg);
     }
     if players.get_by_addr(&data.addr).is_some() {
         let msg = OutputData::AccessDeny(Deny {reason: format!("the address \"{}\" is already used",data.addr)});
-        let serialized = serde_json::to_string(&msg)?;
-        socket.send_to(serialized.as_bytes(),data.addr)?;
+        return send(out, data.addr, &msg);
+    }
+    let humans = players.iter().filter(|p| !p.bot).count();
+    if humans >= max_hosts as usize {
+        let msg = OutputData::AccessDeny(Deny {reason: format!("server full ({}/{})",humans,max_hosts)});
+        return send(out, data.addr, &msg);
     }
-    if players.len() == max_hosts as usize {
-        let msg = OutputData::AccessDeny(Deny {reason: format!("server full ({}/{})",players.len(),max_hosts)});
-        let serialized = serde_json::to_string(&msg)?;
-        socket.send_to(serialized.as_bytes(),data.addr)?;
+    if players.len() >= max_hosts as usize {
+        bots::make_room(session, out)?;
     }
+    let now = session.now_ms();
+    let players = &mut session.players;
     // TODO : add map modularity
     let addr = data.addr;
     // let new_host = PlayerData::init(data, (16.0,16.0,16.0));
-    let mut rng = rand::rng();
-    let spawn = match loader.spawnpoints.choose(&mut rng) {
+    let team = config.game_mode.teams().then(|| teams::assign(players, data.team));
+    let skin = pick_skin(&session.skins, &data.nickname, data.skin.as_deref());
+    let mut new_host = Player::new(data.nickname, (0.0,0.0,0.0), skin);
+    new_host.addr = data.addr;
+    new_host.team = team;
+    let (x, y) = match session.spawns.pick(&session.loader, &session.map, players, &new_host, now) {
         Some(v) => v,
-        None => return Err(format!("can't pick a spawnpoint on connection").into())
+        None => {
+            let msg = OutputData::AccessDeny(Deny {reason: "every spawn point is occupied, try again".to_string()});
+            return send(out, addr, &msg);
+        },
     };
-    let mut new_host = Player::new(data.nickname, (spawn.x as f32 + 0.5,spawn.y as f32 + 0.5,0.0), "goblin");
-    new_host.addr = data.addr;
-    let msg = OutputData::New(new_host.clone());
-    let serialized = serde_json::to_string(&msg)?;
-    // Send new host data to all Players
-    let hosts_without_new = players.clone();
+    new_host.x = x;
+    new_host.y = y;
+    new_host.loadout = Loadout::new(&session.weapons);
+    session.interest.forget(&new_host.nickname);
+    let visible = session.interest.visible_to(&session.map, &new_host, players);
     players.push(new_host.clone());
-    broadcast(socket, Some(addr), players, serialized)?;
 
     // Send other Players data to all other users
-    let msg = OutputData::Connecting((new_host,hosts_without_new.clone(),loader));
-    let serialized = serde_json::to_string(&msg)?;
-    socket.send_to(serialized.as_bytes(), addr)?;
+    let nickname = new_host.nickname.clone();
+    let msg = OutputData::Connecting((new_host,visible,session.loader.clone()));
+    send(out, addr, &msg)?;
+    send(out, addr, &OutputData::Weapons(session.weapons.clone()))?;
+    send(out, addr, &OutputData::Skins(session.skins.clone()))?;
+    if !session.items.is_empty() {
+        send(out, addr, &OutputData::Items(session.items.clone()))?;
+    }
+    send(out, addr, &OutputData::Loadout(Loadout::new(&session.weapons)))?;
+    send(out, addr, &OutputData::Phase(session.phase.clone()))?;
+    if !session.flags.is_empty() {
+        send(out, addr, &OutputData::Flags(flags::public_view(session)))?;
+    }
+    for (nickname, until) in session.spawns.protections(now) {
+        send(out, addr, &OutputData::Protection { nickname: nickname.clone(), until })?;
+    }
+    protect(session, &nickname, out)?;
+    announce(&session.players, out, GameEvent::Join(nickname))?;
+    session.last_seen.insert(addr, now);
     Ok(())
 }
 
 // TODO : Add shooting verification
-pub fn update(players: &mut Players,data: Update,socket: &UdpSocket) -> Result<(),Box<dyn Error>> {
+/// Applies an update and forwards it to the clients that know the player.
+pub fn update(players: &mut Players,interest: &Interest,data: Update,out: &Outbox) -> Result<(),Box<dyn Error>> {
     players.update(&data);
     let msg = OutputData::Update(data.clone());
-    let serialized = serde_json::to_string(&msg)?;
-    broadcast(socket, Some(data.addr), players, serialized)?;
+    broadcast_if(out, Some(data.addr), players, &msg, |p| interest.knows(&p.nickname, &data.nickname))?;
     Ok(())
 }
 
-pub fn disconnection(players: &mut Players, addr: SocketAddr) -> Result<(),Box<dyn Error>> {
+/// Name of the skin given to the player named `nickname`: the one it asked
+/// for if `skins` has it, the first one otherwise.
+fn pick_skin(skins: &[Skin],nickname: &str,wanted: Option<&str>) -> String {
+    let default = skins.first().map_or(Skin::default().name, |s| s.name.clone());
+    match wanted {
+        Some(name) if skins.iter().any(|s| s.name == name) => name.to_string(),
+        Some(name) => {
+            debug!("{} asked for the unknown skin \"{}\", given \"{}\"", nickname, name, default);
+            default
+        },
+        None => default,
+    }
+}
+
+/// Removes the player of `addr` and tells the others it left.
+pub fn disconnection(players: &mut Players, addr: SocketAddr, out: &Outbox) -> Result<(),Box<dyn Error>> {
     let index = match players.get_by_addr(&addr) {
         Some(i) => i,
         None => {
@@ -118,14 +342,46 @@ pub fn disconnection(players: &mut Players, addr: SocketAddr) -> Result<(),Box<d
             return  Err(format!("the ip {} is not connected",addr).into())
         }
     };
+    let data = Update { addr, nickname: players[index].nickname.clone(), x: None, y: None, d: None, status: Some(Status::Disconnecting) };
+    let nickname = data.nickname.clone();
+    broadcast(out, Some(addr), players, &OutputData::Update(data))?;
     players.remove(index);
-    Ok(())
+    announce(players, out, GameEvent::Leave(nickname))
 }
 
-pub fn shoot(players: &mut Players,map: &Map,data: Update,socket: &UdpSocket) -> Result<(),Box<dyn Error>>  {
-    const HIT_RADIUS: f32 = 0.5; // ** A magic variable
-    const DEATH_TIMOUT: u64 = 0;
+/// The `Update` a client sent, made to only describe its own player: the
+/// nickname is the one of the player of `data.addr`, and the status, only
+/// changed by the server on deaths and respawns, is dropped.
+///
+/// # Returns
+/// * `None` if no player has the address.
+pub fn own_update(players: &Players,mut data: Update) -> Option<Update> {
+    let index = players.get_by_addr(&data.addr)?;
+    data.nickname = players[index].nickname.clone();
+    data.status = None;
+    Some(data)
+}
+
+/// Tells if the player named `nickname` is dead, its inputs being ignored until it respawns.
+pub fn is_dead(players: &Players,nickname: &str) -> bool {
+    players.get_by_nickname(&nickname).is_some_and(|i| matches!(players[i].status, Status::Dead(_)))
+}
 
+/// Fires the shot of `data` with the weapon in hand of its player.
+///
+/// The shot is refused by `weapons::fire` if the weapon isn't ready. Each of
+/// its pellets is fired toward the aim, scattered over the spread of the
+/// weapon. A hitscan pellet hurts the first player it meets within range
+/// depending on the distance, the other weapons launch a projectile instead.
+/// Teammates are only hurt if `config.teams.friendly_fire` is set.
+///
+/// The shooter is the player named in `data`, so the shots of clients must
+/// first go through `own_update`.
+pub fn shoot(session: &mut Session,config: &Config,data: Update,out: &Outbox) -> Result<(),Box<dyn Error>>  {
+    let players = &mut session.players;
+    if is_dead(players, &data.nickname) {
+        return Ok(());
+    }
     match players.update(&data) {
         Some(v) => v,
         None => return Err(format!("player \"{}\" does not exist", data.nickname).into())
@@ -134,55 +390,528 @@ pub fn shoot(players: &mut Players,map: &Map,data: Update,socket: &UdpSocket) ->
         Some(v) => v,
         None => return Err(format!("player \"{}\" does not exist", data.nickname).into())
     };
-    let player = match players.get(p_index) {
-        Some(p) => p,
-        None => return Err(format!("no player \"{}\" on index {}", data.nickname, p_index).into())
+    let weapon = match weapons::fire(session, p_index, out)? {
+        Some(w) => w,
+        None => return Ok(()),
     };
-    match player.shoot(map, players, HIT_RADIUS) {
-        Some(target) => {
-            let mut rng = rand::rng();
-            let spawn = match map.spawn_points.choose(&mut rng) {
-                Some(s) => s,
-                None =>  return Err(format!("no spawn point found").into()),
-            };
-            let data = Update { addr:target.addr, nickname: target.nickname.clone(), x: Some(spawn.pos.x as f32 + 0.5), y: Some(spawn.pos.y as f32 + 0.5), d: Some(target.d), status: Some(Status::Dead(DEATH_TIMOUT)) };
-            players.update(&data);
-            let msg = OutputData::Update(data.clone());
-            let serialized = serde_json::to_string(&msg)?;
-            socket.send_to(serialized.as_bytes(), target.addr)?;
-            update(players, data, socket)?;
-            println!("{} has been shot",target.nickname);
-        }
-        None => {}
-    }
-    // update(players, data, socket)?;
+    // Firing ends the spawn protection
+    if session.spawns.unprotect(&data.nickname, session.now_ms()) {
+        let msg = OutputData::Protection { nickname: data.nickname.clone(), until: 0 };
+        broadcast(out, None, &session.players, &msg)?;
+    }
+    let shooter = session.players[p_index].clone();
+    let spread = weapon.spread.to_radians() / 2.0;
+    let mut rng = rand::rng();
+    let mut damages: BTreeMap<String, u32> = BTreeMap::new();
+    for _ in 0..weapon.pellets {
+        let d = if spread > 0.0 { shooter.d + rng.random_range(-spread..=spread) } else { shooter.d };
+        if weapon.projectile.is_some() {
+            projectiles::launch(session, &shooter, &weapon, d, out)?;
+            continue;
+        }
+        let target = match shooter.shoot(&session.map, &session.players, HIT_RADIUS, d, weapon.range) {
+            Some(t) => t,
+            None => continue,
+        };
+        if teams::allies(shooter.team, target.team) && !config.teams.friendly_fire {
+            continue;
+        }
+        *damages.entry(target.nickname.clone()).or_default() += weapon.damage_at((target.x - shooter.x).hypot(target.y - shooter.y));
+    }
+    for (target, damage) in damages {
+        hurt(session, config, &shooter.nickname, &target, &weapon.name, damage, out)?;
+    }
     Ok(())
 }
 
-pub fn running(socket: UdpSocket,instance: &Args) -> Result<(),Box<dyn Error>>  {
-let mut players = Players::new();
-let map_loader = Loader::from_file(&instance.map)?;
-let map = Map::from(&map_loader);
-loop {
-    let data = InputData::parse(&socket)?;
+/// Deals `damage` to the player named `target`, on behalf of `shooter`.
+///
+/// The target loses health and armor and both players receive a `Hit`. A
+/// target left without health dies where it stands and can't respawn before
+/// `config.respawn.delay` is over, everyone being told it was killed with the
+/// weapon named `weapon`. A target under spawn protection isn't hurt.
+pub fn hurt(session: &mut Session,config: &Config,shooter: &str,target: &str,weapon: &str,damage: u32,out: &Outbox) -> Result<(),Box<dyn Error>> {
+    let now = session.now_ms();
+    let respawn_at = now + config.respawn.delay;
+    if session.spawns.protected(target, now) {
+        return Ok(());
+    }
+    let players = &mut session.players;
+    let victim = match players.get_by_nickname(&target) {
+        Some(i) => &mut players.players[i],
+        None => return Err(format!("player \"{}\" does not exist", target).into())
+    };
+    if victim.status != Status::Alive {
+        return Ok(());
+    }
+    let before = victim.health + victim.armor;
+    let fatal = victim.take_damage(damage);
+    let msg = OutputData::Hit(Hit {
+        shooter: shooter.to_string(),
+        target: target.to_string(),
+        damage: before - victim.health - victim.armor,
+        health: victim.health,
+        armor: victim.armor,
+        fatal,
+    });
+    let victim = victim.clone();
+    if let Some(i) = players.get_by_nickname(&shooter) {
+        send_to_player(out, &players[i], &msg)?;
+    }
+    send_to_player(out, &victim, &msg)?;
+    if !fatal {
+        debug!("{} hit {} for {} damage", shooter, target, damage);
+        return Ok(());
+    }
+    let data = Update { addr: victim.addr, nickname: victim.nickname.clone(), x: None, y: None, d: None, status: Some(Status::Dead(respawn_at)) };
+    players.update(&data);
+    let msg = OutputData::Update(data.clone());
+    send_to_player(out, &victim, &msg)?;
+    update(players, &session.interest, data, out)?;
+    scored(session, config, shooter, target);
+    broadcast_scores(session, out)?;
+    announce(&session.players, out, GameEvent::Kill { killer: shooter.to_string(), victim: target.to_string(), weapon: weapon.to_string() })?;
+    info!("{} has been killed by {}", target, shooter);
+    Ok(())
+}
+
+/// Counts the kill of `victim` by `killer`.
+///
+/// Killing oneself is a suicide, costing a point to the player and to its
+/// team. Killing a teammate only counts as a death of the victim. Kills only
+/// score for the team in team deathmatch, teams scoring with captures in
+/// capture-the-flag. Nothing counts during the warmup.
+pub fn scored(session: &mut Session,config: &Config,killer: &str,victim: &str) {
+    if matches!(session.phase, Phase::Warmup { .. }) {
+        return;
+    }
+    let players = &mut session.players;
+    let (k, v) = match (players.get_by_nickname(&killer), players.get_by_nickname(&victim)) {
+        (Some(k), Some(v)) => (k, v),
+        _ => return,
+    };
+    let team = players[k].team;
+    let points = if k == v {
+        players.players[k].stats.suicide();
+        -1
+    } else if teams::allies(team, players[v].team) {
+        players.players[v].stats.death();
+        0
+    } else {
+        players.players[k].stats.kill();
+        players.players[v].stats.death();
+        1
+    };
+    if let (Some(scores), Some(team), GameMode::TeamDeathmatch) = (&mut session.team_scores, team, config.game_mode) {
+        *scores.entry(team).or_default() += points;
+    }
+}
+
+/// The scoreboard of all the players, and of the teams in the team modes, best first.
+pub fn standings(session: &Session) -> Standings {
+    let mut players: Vec<Score> = session.players.iter().map(Score::new).collect();
+    Score::sort(&mut players);
+    let mut teams: Vec<(Team, i32)> = session.team_scores.iter().flatten().map(|(t, s)| (*t, *s)).collect();
+    teams.sort_by_key(|(_, score)| std::cmp::Reverse(*score));
+    Standings { players, teams }
+}
+
+/// Tells every client about `event`, whatever players they see.
+pub fn announce(players: &Players,out: &Outbox,event: GameEvent) -> Result<(),Box<dyn Error>> {
+    broadcast(out, None, players, &OutputData::Event(event))
+}
+
+/// Sends every client the scoreboard of all the players, visible or not.
+pub fn broadcast_scores(session: &Session,out: &Outbox) -> Result<(),Box<dyn Error>> {
+    broadcast(out, None, &session.players, &OutputData::Scores(standings(session)))
+}
+
+/// Brings the dead player of `index` back on a spawn point of its team with
+/// full health and full magazines, under spawn protection.
+///
+/// While every spawn point is occupied, a dead player's respawn waits until
+/// it asks again or the next tick for the automatic respawns. A living one,
+/// as at the start of a match, is brought back where it stands.
+pub fn respawn(session: &mut Session,index: usize,out: &Outbox) -> Result<(),Box<dyn Error>> {
+    let player = match session.players.get(index) {
+        Some(p) => p.clone(),
+        None => return Err(format!("no player on index {}", index).into()),
+    };
+    let now = session.now_ms();
+    let (x, y) = match session.spawns.pick(&session.loader, &session.map, &session.players, &player, now) {
+        Some(s) => s,
+        None if player.status == Status::Alive => (player.x, player.y),
+        None => {
+            debug!("every spawn point is occupied, {} waits to respawn", player.nickname);
+            return Ok(());
+        },
+    };
+    let players = &mut session.players;
+    players.players[index].health = MAX_HEALTH;
+    players.players[index].armor = 0;
+    weapons::refill(&mut players.players[index].loadout, &session.weapons);
+    let data = Update { addr: player.addr, nickname: player.nickname.clone(), x: Some(x), y: Some(y), d: Some(player.d), status: Some(Status::Alive) };
+    players.update(&data);
+    send_to_player(out, &player, &OutputData::Update(data.clone()))?;
+    weapons::send(session, index, out)?;
+    protect(session, &player.nickname, out)?;
+    update(&mut session.players, &session.interest, data, out)
+}
+
+/// Puts the player named `nickname`, who just spawned, under spawn protection
+/// and tells every client until when.
+pub fn protect(session: &mut Session,nickname: &str,out: &Outbox) -> Result<(),Box<dyn Error>> {
+    let until = session.spawns.protect(nickname, session.now_ms());
+    broadcast(out, None, &session.players, &OutputData::Protection { nickname: nickname.to_string(), until })
+}
+
+/// Handles the respawn request of a client, refused while its delay isn't over.
+pub fn respawn_request(session: &mut Session,addr: SocketAddr,out: &Outbox) -> Result<(),Box<dyn Error>> {
+    let index = match session.players.get_by_addr(&addr) {
+        Some(i) => i,
+        None => return Err(format!("respawn request from unknown address {}", addr).into()),
+    };
+    match session.players[index].status {
+        Status::Dead(time) if time <= session.now_ms() => respawn(session, index, out),
+        _ => Ok(()),
+    }
+}
+
+/// Removes a player from the session and tells everyone about it.
+///
+/// The kicked client receives an `AccessDeny` carrying `reason`, the others
+/// an update with the `Disconnecting` status and a `Leave` event.
+pub fn kick(players: &mut Players,out: &Outbox,index: usize,reason: String) -> Result<(),Box<dyn Error>> {
+    let target = match players.get(index) {
+        Some(p) => p.clone(),
+        None => return Err(format!("no player on index {}", index).into()),
+    };
+    send_to_player(out, &target, &OutputData::AccessDeny(Deny { reason }))?;
+    let data = Update { addr: target.addr, nickname: target.nickname.clone(), x: None, y: None, d: None, status: Some(Status::Disconnecting) };
+    broadcast(out, Some(target.addr), players, &OutputData::Update(data))?;
+    players.remove(index);
+    announce(players, out, GameEvent::Leave(target.nickname))
+}
+
+/// Switches the session to the map stored at `path` while players are connected.
+///
+/// Every player is respawned on one of the new map's spawn points for its team, then each
+/// client receives a `MapChange` with its own player, the others and the new
+/// `Loader` so it can rebuild its map and textures without reconnecting. Everyone
+/// starts the new map under spawn protection, except the players left without
+/// a free spawn point, who wait dead to respawn.
+pub fn change_map(session: &mut Session,out: &Outbox,path: &str) -> Result<(),Box<dyn Error>> {
+    let loader = Loader::from_file(path)?;
+    if loader.spawnpoints.is_empty() {
+        return Err(format!("map \"{}\" has no spawnpoint", path).into());
+    }
+    let map = Map::from(&loader);
+    let now = session.now_ms();
+    session.spawns.reset();
+    // Each player avoids the spawn points of the ones placed before it
+    let mut placed = Players::new();
+    for player in session.players.players.iter_mut() {
+        match session.spawns.pick(&loader, &map, &placed, player, now) {
+            Some((x, y)) => {
+                player.x = x;
+                player.y = y;
+                player.status = Status::Alive;
+                player.health = MAX_HEALTH;
+                player.armor = 0;
+                weapons::refill(&mut player.loadout, &session.weapons);
+            },
+            // Out of free spawn points, the player waits dead on the first one to respawn
+            None => {
+                let spawn = &loader.spawnpoints[0];
+                (player.x, player.y) = (spawn.x as f32 + 0.5, spawn.y as f32 + 0.5);
+                player.status = Status::Dead(now);
+            },
+        }
+        placed.push(player.clone());
+    }
+    session.map = map;
+    if !session.flags.is_empty() {
+        session.flags = flags::at_bases(&loader);
+    }
+    session.items = items::on_map(&loader);
+    session.loader = loader;
+    session.bots.reset();
+    session.projectiles.clear();
+    session.map_path = path.to_string();
+    let time = session.now_ms();
+    if let Some(demo) = &session.demo {
+        demo.lock().map_err(|_| "the demo is poisoned")?.snapshot(time, &session.loader, &session.players)?;
+    }
+    for player in session.players.iter() {
+        let others = session.interest.visible_to(&session.map, player, &session.players);
+        let msg = OutputData::MapChange((player.clone(), others, session.loader.clone()));
+        send_to_player(out, player, &msg)?;
+        send_to_player(out, player, &OutputData::Loadout(player.loadout.clone()))?;
+    }
+    if !session.flags.is_empty() {
+        flags::broadcast(session, out)?;
+    }
+    items::broadcast(session, out)?;
+    let nicknames: Vec<String> = session.players.iter().filter(|p| p.status == Status::Alive).map(|p| p.nickname.clone()).collect();
+    for nickname in nicknames {
+        protect(session, &nickname, out)?;
+    }
+    info!("map changed to {}", path);
+    Ok(())
+}
+
+/// Switches the session to the next map of the rotation.
+pub fn next_map(session: &mut Session,out: &Outbox) -> Result<(),Box<dyn Error>> {
+    let path = session.next_in_rotation();
+    change_map(session, out, &path)
+}
+
+/// Runs an admin command against the session.
+///
+/// Called from the simulation loop only, so the game state is never shared
+/// with the admin socket threads.
+pub fn admin(session: &mut Session,out: &Outbox,config: &Config,command: AdminCommand) -> AdminResponse {
+    let result: Result<AdminResponse,Box<dyn Error>> = match command {
+        AdminCommand::Status => Ok(AdminResponse::Status(ServerStatus {
+            name: config.name.clone(),
+            map: session.map_path.clone(),
+            rotation: session.rotation.clone(),
+            uptime: session.now_ms() / 1000,
+            max_hosts: config.max_hosts,
+            players: session.players.iter().map(|p| PlayerStatus { nickname: p.nickname.clone(), addr: p.addr, x: p.x, y: p.y, status: p.status, bot: p.bot, team: p.team, stats: p.stats }).collect(),
+            bans: session.bans.iter().copied().collect(),
+        })),
+        AdminCommand::Kick { nickname, reason } => match session.players.get_by_nickname(&nickname) {
+            Some(index) => {
+                let reason = reason.unwrap_or("kicked by an administrator".to_string());
+                kick(&mut session.players, out, index, reason).map(|_| AdminResponse::Done(format!("{} has been kicked", nickname)))
+            },
+            None => Ok(AdminResponse::Error(format!("player \"{}\" does not exist", nickname))),
+        },
+        AdminCommand::Ban { nickname, reason } => match session.players.get_by_nickname(&nickname) {
+            Some(index) => {
+                let ip = session.players[index].addr.ip();
+                if let Err(e) = session.bans.insert(ip) {
+                    warn!("can't save the ban list: {}", e);
+                }
+                let reason = reason.unwrap_or("banned by an administrator".to_string());
+                kick(&mut session.players, out, index, reason).map(|_| AdminResponse::Done(format!("{} ({}) has been banned", nickname, ip)))
+            },
+            None => Ok(AdminResponse::Error(format!("player \"{}\" does not exist", nickname))),
+        },
+        AdminCommand::Unban { ip } => session.bans.remove(&ip).map(|removed| match removed {
+            true => AdminResponse::Done(format!("{} has been unbanned", ip)),
+            false => AdminResponse::Error(format!("{} is not banned", ip)),
+        }),
+        AdminCommand::ChangeMap { path } => change_map(session, out, &path)
+            .map(|_| AdminResponse::Done(format!("map changed to {}", path))),
+        AdminCommand::NextMap => next_map(session, out)
+            .map(|_| AdminResponse::Done(format!("map changed to {}", session.map_path))),
+        AdminCommand::Broadcast { message } => broadcast(out, None, &session.players, &OutputData::Message(message))
+            .map(|_| AdminResponse::Done(format!("message sent to {} players", session.players.len()))),
+    };
+    result.unwrap_or_else(|e| AdminResponse::Error(e.to_string()))
+}
+
+/// Stores the round trip time measured from a `Pong`.
+pub fn pong(session: &mut Session,addr: SocketAddr,time: u64) -> Result<(),Box<dyn Error>> {
+    let now = session.now_ms();
+    let index = match session.players.get_by_addr(&addr) {
+        Some(i) => i,
+        None => return Err(format!("pong from unknown address {}", addr).into()),
+    };
+    if time > now {
+        return Err(format!("pong from {} carries a time in the future", addr).into());
+    }
+    session.players.players[index].ping = (now - time) as u32;
+    Ok(())
+}
+
+/// Runs the work that doesn't depend on incoming packets, once per tick.
+///
+/// Players silent for longer than the idle timeout are kicked, and every
+/// `PING_INTERVAL` the players are pinged, the scoreboard is sent with the
+/// new pings and the metrics updated.
+pub fn tick(session: &mut Session,out: &Outbox,config: &Config) -> Result<(),Box<dyn Error>> {
+    let now = session.now_ms();
+    let idle = config.timeouts.idle * 1000;
+    let timed_out: Vec<SocketAddr> = session.last_seen.iter()
+        .filter(|(_, seen)| now.saturating_sub(**seen) > idle)
+        .map(|(addr, _)| *addr)
+        .collect();
+    for addr in timed_out {
+        session.last_seen.remove(&addr);
+        if let Some(index) = session.players.get_by_addr(&addr) {
+            info!("{} timed out", session.players[index].nickname);
+            kick(&mut session.players, out, index, "timed out".to_string())?;
+        }
+    }
+    session.limiter.prune(now);
+    bots::fill(session, out, config)?;
+    for index in 0..session.players.len() {
+        let player = &session.players[index];
+        if matches!(player.status, Status::Dead(time) if time <= now) && (player.bot || config.respawn.auto) {
+            respawn(session, index, out)?;
+        }
+    }
+    lifecycle::advance(session, out, config)?;
+    if session.phase.playing() {
+        bots::think(session, out, config)?;
+        if !session.projectiles.is_empty() {
+            projectiles::update(session, out, config)?;
+        }
+        if !session.items.is_empty() {
+            items::update(session, out)?;
+        }
+        if !session.flags.is_empty() {
+            flags::update(session, out, config)?;
+        }
+    }
+    interest::refresh(session, out)?;
+    let time = session.now_ms();
+    if let Some(demo) = &session.demo {
+        demo.lock().map_err(|_| "the demo is poisoned")?.capture(time, &session.loader, &session.players)?;
+    }
+    if time.saturating_sub(session.last_ping) >= PING_INTERVAL {
+        session.last_ping = time;
+        broadcast(out, None, &session.players, &OutputData::Ping(session.now_ms()))?;
+        broadcast_scores(session, out)?;
+        metrics::players(session.players.iter().filter(|p| !p.bot).map(|p| (p.nickname.as_str(), Duration::from_millis(p.ping as u64))));
+    }
+    if let Some(interval) = config.metrics.summary_interval {
+        if time.saturating_sub(session.last_summary) >= interval * 1000 {
+            session.last_summary = time;
+            info!("metrics: {}", metrics::take_summary());
+        }
+    }
+    Ok(())
+}
+
+/// Runs the server on `socket` until `shutdown` is set.
+///
+/// Receiving and decoding packets, and encoding and sending messages, each
+/// run on their own thread. The simulation runs on the calling thread and
+/// only talks to them through channels, so packet bursts or slow sends can't
+/// delay a tick.
+pub fn running<T: Transport>(socket: T,config: &Config,admin_queue: Option<AdminQueue>,shutdown: &AtomicBool) -> Result<(),Box<dyn Error>>  {
+    let socket: &dyn Transport = &socket;
+    let stop = AtomicBool::new(false);
+    thread::scope(|scope| {
+        let (inbox_tx, inbox) = channel();
+        let (outbox_tx, outbox_rx) = channel();
+        let receiver = scope.spawn(|| net::receiving(socket, inbox_tx, &stop).map_err(|e| e.to_string()));
+        scope.spawn(|| net::sending(socket, outbox_rx));
+        let result = simulate(inbox, Outbox::new(outbox_tx), config, admin_queue, shutdown);
+        stop.store(true, Ordering::Relaxed);
+        match receiver.join() {
+            Ok(Err(e)) => Err(format!("network error: {}", e).into()),
+            _ => result,
+        }
+    })
+}
+
+/// Runs the game until `shutdown` is set, then disconnects every player.
+///
+/// # Arguments
+/// * `inbox` - The packets decoded by the receiving thread.
+/// * `out` - The queue of the sending thread. Dropping it stops that thread.
+pub fn simulate(inbox: Inbox,out: Outbox,config: &Config,admin_queue: Option<AdminQueue>,shutdown: &AtomicBool) -> Result<(),Box<dyn Error>> {
+    let mut session = Session::new(config, Clock::real())?;
+    let out = match &session.demo {
+        Some(demo) => out.recording(demo.clone(), session.clock.clone()),
+        None => out,
+    };
+    let tick_duration = config.tick_duration();
+    let mut next_tick = Instant::now() + tick_duration;
+    while !shutdown.load(Ordering::Relaxed) {
+        if let Some(queue) = &admin_queue {
+            while let Ok((command, reply)) = queue.try_recv() {
+                let _ = reply.send(admin(&mut session, &out, config, command));
+            }
+        }
+        let now = Instant::now();
+        if now >= next_tick {
+            if let Err(e) = tick(&mut session, &out, config) {
+                error!("{}", e);
+            }
+            metrics::tick(now.elapsed());
+            next_tick += tick_duration;
+            if next_tick < now {
+                next_tick = now + tick_duration;
+            }
+        }
+        let (data, size) = match inbox.recv_timeout(next_tick.saturating_duration_since(now)) {
+            Ok(v) => v,
+            Err(RecvTimeoutError::Timeout) => continue,
+            Err(RecvTimeoutError::Disconnected) => break,
+        };
+        if let Err(e) = handle(&mut session, &out, config, data, size) {
+            error!("{}", e);
+        }
+    }
+    info!("shutting down");
+    let msg = OutputData::AccessDeny(Deny { reason: "the server has been shut down".to_string() });
+    broadcast(&out, None, &session.players, &msg)
+}
+
+/// Applies a packet of `size` bytes received by the server, dropped if its
+/// sender is over its rate limit.
+pub fn handle(session: &mut Session,out: &Outbox,config: &Config,data: InputData,size: usize) -> Result<(),Box<dyn Error>> {
+    let now = session.now_ms();
+    if !matches!(data, InputData::None | InputData::Unknown) {
+        metrics::received(data.kind(), size);
+    }
+    if let Some(addr) = data.addr() {
+        if !session.limiter.allow(addr, now) {
+            debug!("{} is over its rate limit, packet dropped", addr);
+            metrics::rate_limited();
+            return Ok(());
+        }
+        if let Some(seen) = session.last_seen.get_mut(&addr) {
+            *seen = now;
+        }
+    }
     match data {
         InputData::Connection(data) => {
             let addr = data.addr;
-            connection(&mut players, data, &socket, instance.max_hosts,map_loader.clone())?;
-            println!("{:?}: connection", addr);
+            info!("{:?}: connection", addr);
+            connection(session, data, out, config)
         },
-        InputData::Update(data) => {
-            update(&mut players, data, &socket)?;
+        InputData::Update(data) => match own_update(&session.players, data) {
+            Some(data) if is_dead(&session.players, &data.nickname) => Ok(()),
+            Some(data) => update(&mut session.players, &session.interest, data, out),
+            None => Ok(()),
         },
         InputData::Disconnection {addr} => {
-            disconnection(&mut players, addr)?;
-            println!("the player of addr : {} has been succesfully removed",addr)
+            session.last_seen.remove(&addr);
+            disconnection(&mut session.players, addr, out)
+                .map(|_| info!("the player of addr : {} has been succesfully removed",addr))
+        }
+        InputData::Shoot(_) if !session.phase.playing() => Ok(()),
+        InputData::Shoot(data) => match own_update(&session.players, data) {
+            Some(data) => shoot(session, config, data, out),
+            None => Ok(()),
+        },
+        InputData::Switch { addr, weapon } => {
+            weapons::switch(session, addr, weapon, out)
+        }
+        InputData::Reload { addr } => {
+            weapons::reload(session, addr, out)
+        }
+        InputData::Respawn { addr } => {
+            respawn_request(session, addr, out)
         }
-        InputData::Shoot(data) => {
-            shoot(&mut players,&map,data, &socket)?;
+        InputData::Pong { addr, time } => {
+            pong(session, addr, time)
         }
-        InputData::None => (),
-        InputData::Unknown => eprintln!("malformed request :\n{:#?}",data),
+        InputData::Ping { addr, time } => {
+            send(out, addr, &OutputData::Pong { client: time, server: now, received: None })
+        }
+        InputData::None => Ok(()),
+        InputData::Unknown => {
+            warn!("malformed request of {} bytes",size);
+            metrics::malformed();
+            Ok(())
+        },
     }
 }
-}
\ No newline at end of file
@@ -1,14 +1,22 @@
-use std::{error::Error, net::{Ipv4Addr, UdpSocket}, time::Duration};
+use std::{error::Error, net::{Ipv4Addr, UdpSocket}, sync::atomic::AtomicBool};
 use clap::Parser;
-pub mod args;
-pub mod logic;
+use multiplayer_fps::info;
+use multiplayer_fps::server::{admin, args, config, log, logic, metrics};
 
 
 fn main() -> Result<(),Box<dyn Error>> {
     let args = args::Args::parse();
-    let socket = UdpSocket::bind(format!("{}:{}",Ipv4Addr::new(0, 0, 0, 0),args.port))?;
-    socket.set_read_timeout(Some(Duration::from_secs(5)))?;
-    println!("running server on port {}",socket.local_addr()?.port());
-    logic::running(socket, &args)?;
+    let config = config::Config::load(&args)?;
+    log::set_level(config.log_level);
+    let socket = UdpSocket::bind(format!("{}:{}",Ipv4Addr::new(0, 0, 0, 0),config.port))?;
+    let admin_queue = match &config.admin {
+        Some(admin) => Some(admin::listen(admin.port, admin.password.clone())?),
+        None => None,
+    };
+    if let Some(port) = config.metrics.port {
+        metrics::listen(port)?;
+    }
+    info!("running server \"{}\" on port {}",config.name,socket.local_addr()?.port());
+    logic::running(socket, &config, admin_queue, &AtomicBool::new(false))?;
     Ok(())
-}
\ No newline at end of file
+}
@@ -0,0 +1,230 @@
+use std::{
+    collections::BTreeMap,
+    fmt::Write as _,
+    io::{BufRead, BufReader, Write},
+    net::{Ipv4Addr, TcpListener, TcpStream},
+    sync::{Mutex, MutexGuard},
+    thread,
+    time::Duration,
+};
+
+/// Counters shared by the simulation and the exporter thread.
+static METRICS: Mutex<Metrics> = Mutex::new(Metrics::new());
+
+#[derive(Clone, Copy, Default)]
+struct Traffic {
+    packets: u64,
+    bytes: u64,
+}
+
+/// Everything the exporter publishes.
+///
+/// Counters only grow, except the `window_*` values which are reset every
+/// time a summary line is logged.
+pub struct Metrics {
+    ticks: u64,
+    tick_seconds: f64,
+    last_tick: f64,
+    received: BTreeMap<&'static str, Traffic>,
+    sent: BTreeMap<&'static str, Traffic>,
+    malformed: u64,
+    rate_limited: u64,
+    players: usize,
+    rtt: BTreeMap<String, Duration>,
+    window_ticks: u64,
+    window_tick_seconds: f64,
+    window_max_tick: f64,
+    window_received: Traffic,
+    window_sent: Traffic,
+}
+
+impl Metrics {
+    const fn new() -> Self {
+        Self {
+            ticks: 0,
+            tick_seconds: 0.0,
+            last_tick: 0.0,
+            received: BTreeMap::new(),
+            sent: BTreeMap::new(),
+            malformed: 0,
+            rate_limited: 0,
+            players: 0,
+            rtt: BTreeMap::new(),
+            window_ticks: 0,
+            window_tick_seconds: 0.0,
+            window_max_tick: 0.0,
+            window_received: Traffic { packets: 0, bytes: 0 },
+            window_sent: Traffic { packets: 0, bytes: 0 },
+        }
+    }
+
+    /// Renders the metrics in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "# HELP fps_tick_duration_seconds Time spent in the simulation ticks.");
+        let _ = writeln!(out, "# TYPE fps_tick_duration_seconds summary");
+        let _ = writeln!(out, "fps_tick_duration_seconds_sum {}", self.tick_seconds);
+        let _ = writeln!(out, "fps_tick_duration_seconds_count {}", self.ticks);
+        let _ = writeln!(out, "# HELP fps_last_tick_duration_seconds Duration of the last simulation tick.");
+        let _ = writeln!(out, "# TYPE fps_last_tick_duration_seconds gauge");
+        let _ = writeln!(out, "fps_last_tick_duration_seconds {}", self.last_tick);
+        render_traffic(&mut out, "received", &self.received);
+        render_traffic(&mut out, "sent", &self.sent);
+        let _ = writeln!(out, "# HELP fps_malformed_packets_total Packets that couldn't be decoded.");
+        let _ = writeln!(out, "# TYPE fps_malformed_packets_total counter");
+        let _ = writeln!(out, "fps_malformed_packets_total {}", self.malformed);
+        let _ = writeln!(out, "# HELP fps_rate_limited_packets_total Packets dropped by the rate limiter.");
+        let _ = writeln!(out, "# TYPE fps_rate_limited_packets_total counter");
+        let _ = writeln!(out, "fps_rate_limited_packets_total {}", self.rate_limited);
+        let _ = writeln!(out, "# HELP fps_players Connected players.");
+        let _ = writeln!(out, "# TYPE fps_players gauge");
+        let _ = writeln!(out, "fps_players {}", self.players);
+        let _ = writeln!(out, "# HELP fps_player_rtt_seconds Last round trip time measured for each player.");
+        let _ = writeln!(out, "# TYPE fps_player_rtt_seconds gauge");
+        for (nickname, rtt) in &self.rtt {
+            let _ = writeln!(out, "fps_player_rtt_seconds{{nickname=\"{}\"}} {}", escape(nickname), rtt.as_secs_f64());
+        }
+        out
+    }
+
+    /// One line describing the activity since the previous summary.
+    fn summary(&self) -> String {
+        let avg = if self.window_ticks == 0 { 0.0 } else { self.window_tick_seconds / self.window_ticks as f64 };
+        format!(
+            "players={} ticks={} avg_tick={:.3}ms max_tick={:.3}ms in={}pkt/{}B out={}pkt/{}B malformed={} rate_limited={}",
+            self.players,
+            self.window_ticks,
+            avg * 1000.0,
+            self.window_max_tick * 1000.0,
+            self.window_received.packets,
+            self.window_received.bytes,
+            self.window_sent.packets,
+            self.window_sent.bytes,
+            self.malformed,
+            self.rate_limited,
+        )
+    }
+}
+
+fn render_traffic(out: &mut String, direction: &str, traffic: &BTreeMap<&'static str, Traffic>) {
+    let _ = writeln!(out, "# HELP fps_packets_{}_total Packets {} per message type.", direction, direction);
+    let _ = writeln!(out, "# TYPE fps_packets_{}_total counter", direction);
+    for (kind, t) in traffic {
+        let _ = writeln!(out, "fps_packets_{}_total{{type=\"{}\"}} {}", direction, kind, t.packets);
+    }
+    let _ = writeln!(out, "# HELP fps_bytes_{}_total Bytes {} per message type.", direction, direction);
+    let _ = writeln!(out, "# TYPE fps_bytes_{}_total counter", direction);
+    for (kind, t) in traffic {
+        let _ = writeln!(out, "fps_bytes_{}_total{{type=\"{}\"}} {}", direction, kind, t.bytes);
+    }
+}
+
+/// Escapes a Prometheus label value.
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+fn metrics() -> MutexGuard<'static, Metrics> {
+    METRICS.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Records the duration of a simulation tick.
+pub fn tick(duration: Duration) {
+    let mut m = metrics();
+    let secs = duration.as_secs_f64();
+    m.ticks += 1;
+    m.tick_seconds += secs;
+    m.last_tick = secs;
+    m.window_ticks += 1;
+    m.window_tick_seconds += secs;
+    m.window_max_tick = m.window_max_tick.max(secs);
+}
+
+/// Records a decoded packet of type `kind`.
+pub fn received(kind: &'static str, bytes: usize) {
+    let mut m = metrics();
+    let t = m.received.entry(kind).or_default();
+    t.packets += 1;
+    t.bytes += bytes as u64;
+    m.window_received.packets += 1;
+    m.window_received.bytes += bytes as u64;
+}
+
+/// Records a packet of type `kind` sent to one client.
+pub fn sent(kind: &'static str, bytes: usize) {
+    let mut m = metrics();
+    let t = m.sent.entry(kind).or_default();
+    t.packets += 1;
+    t.bytes += bytes as u64;
+    m.window_sent.packets += 1;
+    m.window_sent.bytes += bytes as u64;
+}
+
+pub fn malformed() {
+    metrics().malformed += 1;
+}
+
+pub fn rate_limited() {
+    metrics().rate_limited += 1;
+}
+
+/// Replaces the player count and the round trip times.
+pub fn players<'a, I: Iterator<Item = (&'a str, Duration)>>(rtt: I) {
+    let mut m = metrics();
+    m.rtt = rtt.map(|(nickname, d)| (nickname.to_string(), d)).collect();
+    m.players = m.rtt.len();
+}
+
+/// Returns the summary of the activity since the last call and starts a new window.
+pub fn take_summary() -> String {
+    let mut m = metrics();
+    let line = m.summary();
+    m.window_ticks = 0;
+    m.window_tick_seconds = 0.0;
+    m.window_max_tick = 0.0;
+    m.window_received = Traffic::default();
+    m.window_sent = Traffic::default();
+    line
+}
+
+/// Serves the metrics over HTTP on `127.0.0.1:port`.
+///
+/// Any request gets the Prometheus text page, so the exporter can be scraped
+/// from any path.
+pub fn listen(port: u16) -> std::io::Result<()> {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))?;
+    info!("metrics exporter listening on {}", listener.local_addr()?);
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    if let Err(e) = serve(stream) {
+                        debug!("metrics request failed: {}", e);
+                    }
+                },
+                Err(e) => warn!("metrics exporter: {}", e),
+            }
+        }
+    });
+    Ok(())
+}
+
+fn serve(mut stream: TcpStream) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(Duration::from_secs(2)))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut line = String::new();
+    // Skip the request line and the headers
+    while reader.read_line(&mut line)? > 0 {
+        if line == "\r\n" || line == "\n" {
+            break;
+        }
+        line.clear();
+    }
+    let body = metrics().render();
+    write!(
+        stream,
+        "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        body.len(),
+        body
+    )
+}
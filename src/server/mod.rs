@@ -1,2 +1,21 @@
-// pub mod logic;
-// pub mod args;
\ No newline at end of file
+#[macro_use]
+pub mod log;
+pub mod admin;
+pub mod args;
+pub mod bans;
+pub mod bots;
+pub mod clock;
+pub mod config;
+pub mod flags;
+pub mod instance;
+pub mod interest;
+pub mod items;
+pub mod lifecycle;
+pub mod limits;
+pub mod logic;
+pub mod metrics;
+pub mod net;
+pub mod projectiles;
+pub mod spawns;
+pub mod teams;
+pub mod weapons;
@@ -0,0 +1,111 @@
+use std::{
+    error::Error,
+    net::SocketAddr,
+    sync::{atomic::{AtomicBool, Ordering}, mpsc::{Receiver, Sender}, Arc, Mutex},
+    time::Duration,
+};
+
+use crate::{data::{InputData, OutputData}, demo::DemoWriter, transport::Transport};
+
+use super::clock::Clock;
+use super::metrics;
+
+/// Longest time the receiving thread waits before checking if it must stop.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// A message and the addresses it must be sent to.
+pub struct Outgoing {
+    pub to: Vec<SocketAddr>,
+    pub msg: OutputData,
+}
+
+/// Decoded messages with their size in bytes, read by the simulation.
+pub type Inbox = Receiver<(InputData, usize)>;
+
+/// The demo of the match, written by the simulation and by its `Outbox`.
+pub type Recorder = Arc<Mutex<DemoWriter>>;
+
+#[derive(Clone)]
+/// Queue of the messages the simulation wants sent.
+///
+/// Pushing never blocks: serializing and writing to the socket is left to
+/// the sending thread, so a slow send can't stall a tick.
+///
+/// If the match is recorded, every broadcast message is also written to the
+/// demo, timed with the clock of the session.
+pub struct Outbox {
+    tx: Sender<Outgoing>,
+    recorder: Option<(Recorder, Clock)>,
+}
+
+impl Outbox {
+    pub fn new(tx: Sender<Outgoing>) -> Self {
+        Self { tx, recorder: None }
+    }
+
+    /// Records the broadcast messages to `demo`, timed with `clock`.
+    pub fn recording(self, demo: Recorder, clock: Clock) -> Self {
+        Self { recorder: Some((demo, clock)), ..self }
+    }
+
+    /// Writes a broadcast message to the demo, if the match is recorded.
+    ///
+    /// Pings only measure the round trip time of the clients and are left out.
+    pub fn record(&self, msg: &OutputData) -> Result<(), Box<dyn Error>> {
+        match &self.recorder {
+            Some(_) if matches!(msg, OutputData::Ping(_)) => Ok(()),
+            Some((demo, clock)) => {
+                let time = clock.now_ms();
+                demo.lock().map_err(|_| "the demo is poisoned")?.record(time, msg)
+            },
+            None => Ok(()),
+        }
+    }
+
+    /// Queues `msg` for every address of `to`. It is serialized only once.
+    pub fn send(&self, to: Vec<SocketAddr>, msg: OutputData) -> Result<(), Box<dyn Error>> {
+        if to.is_empty() {
+            return Ok(());
+        }
+        self.tx.send(Outgoing { to, msg }).map_err(|_| "the sending thread has stopped".into())
+    }
+}
+
+/// Reads and decodes the incoming packets until `stop` is set or the inbox is dropped.
+///
+/// # Returns
+/// * `Err` if the socket fails, which stops the server.
+pub fn receiving(socket: &dyn Transport, inbox: Sender<(InputData, usize)>, stop: &AtomicBool) -> Result<(), Box<dyn Error>> {
+    socket.set_read_timeout(Some(POLL_INTERVAL))?;
+    while !stop.load(Ordering::Relaxed) {
+        let (data, size) = InputData::receive(socket)?;
+        if matches!(data, InputData::None) {
+            continue;
+        }
+        if inbox.send((data, size)).is_err() {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Serializes and sends the queued messages until every `Outbox` is dropped.
+///
+/// A failed send only loses that packet, as any UDP packet could be lost.
+pub fn sending(socket: &dyn Transport, outbox: Receiver<Outgoing>) {
+    for Outgoing { to, msg } in outbox {
+        let serialized = match serde_json::to_string(&msg) {
+            Ok(s) => s,
+            Err(e) => {
+                error!("can't serialize a {} message: {}", msg.kind(), e);
+                continue;
+            }
+        };
+        for addr in to {
+            match socket.send_to(serialized.as_bytes(), addr) {
+                Ok(_) => metrics::sent(msg.kind(), serialized.len()),
+                Err(e) => debug!("can't send a {} message to {}: {}", msg.kind(), addr, e),
+            }
+        }
+    }
+}
@@ -0,0 +1,147 @@
+use std::{collections::BTreeMap, error::Error};
+
+use crate::{data::{OutputData, Projectile, Status}, entities::{Player, Weapon, HIT_RADIUS}};
+
+use super::config::Config;
+use super::net::Outbox;
+use super::logic::{self, Session};
+use super::teams;
+
+/// Distance, in tiles, a projectile is moved by at once when checking its collisions.
+const STEP: f32 = 0.1;
+
+/// A projectile simulated by the server.
+///
+/// # Fields
+/// - `projectile`: The state sent to the clients.
+/// - `weapon`: The weapon it was fired with.
+/// - `traveled`: Tiles travelled so far.
+struct Flight {
+    projectile: Projectile,
+    weapon: Weapon,
+    traveled: f32,
+}
+
+/// The projectiles in flight in a session.
+pub struct Projectiles {
+    flights: Vec<Flight>,
+    next_id: u32,
+}
+
+impl Default for Projectiles {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Projectiles {
+    pub fn new() -> Self {
+        Self { flights: Vec::new(), next_id: 1 }
+    }
+
+    /// Removes every projectile, for instance after a map change.
+    pub fn clear(&mut self) {
+        self.flights.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.flights.is_empty()
+    }
+}
+
+/// Fires a projectile of `weapon` from `shooter` toward `d`, and tells about
+/// it the shooter and the clients knowing the shooter, the projectile giving
+/// away its position.
+pub fn launch(session: &mut Session,shooter: &Player,weapon: &Weapon,d: f32,out: &Outbox) -> Result<(),Box<dyn Error>> {
+    let ballistics = match &weapon.projectile {
+        Some(b) => b,
+        None => return Err(format!("the {} fires no projectile", weapon.name).into()),
+    };
+    let now = session.now_ms();
+    let projectile = Projectile {
+        id: session.projectiles.next_id,
+        owner: shooter.nickname.clone(),
+        x: shooter.x,
+        y: shooter.y,
+        d,
+        speed: ballistics.speed,
+        fired_at: now,
+        until: now + (weapon.range / ballistics.speed * 1000.0) as u64,
+        texture: ballistics.texture.clone(),
+    };
+    session.projectiles.next_id = session.projectiles.next_id.wrapping_add(1);
+    let msg = OutputData::Projectile(projectile.clone());
+    logic::broadcast_if(out, None, &session.players, &msg, |p| p.nickname == shooter.nickname || session.interest.knows(&p.nickname, &shooter.nickname))?;
+    session.projectiles.flights.push(Flight { projectile, weapon: weapon.clone(), traveled: 0.0 });
+    Ok(())
+}
+
+/// Moves every projectile along its flight, once per tick.
+///
+/// A projectile explodes on the first wall or living player it meets, its
+/// owner excluded, or at the end of the range of its weapon.
+pub fn update(session: &mut Session,out: &Outbox,config: &Config) -> Result<(),Box<dyn Error>> {
+    let now = session.now_ms();
+    let mut explosions = Vec::new();
+    for flight in session.projectiles.flights.iter_mut() {
+        let goal = flight.projectile.traveled_at(now).min(flight.weapon.range);
+        let mut impact = None;
+        while flight.traveled < goal && impact.is_none() {
+            let next = (flight.traveled + STEP).min(goal);
+            let (x, y) = flight.projectile.position_after(next);
+            if session.map.is_wall(x.floor() as i32, y.floor() as i32) != Some(false) {
+                // Explodes in front of the wall, so the wall doesn't shield the players next to it
+                impact = Some(flight.projectile.position_after(flight.traveled));
+            } else if session.players.iter().any(|p| p.nickname != flight.projectile.owner && p.status == Status::Alive && (p.x - x).hypot(p.y - y) <= HIT_RADIUS) {
+                impact = Some((x, y));
+            }
+            flight.traveled = next;
+        }
+        if impact.is_none() && flight.traveled >= flight.weapon.range {
+            impact = Some(flight.projectile.position_after(flight.traveled));
+        }
+        if let Some(at) = impact {
+            explosions.push((flight.projectile.id, at));
+        }
+    }
+    for (id, at) in explosions {
+        if let Some(index) = session.projectiles.flights.iter().position(|f| f.projectile.id == id) {
+            let flight = session.projectiles.flights.remove(index);
+            explode(session, config, &flight, at, out)?;
+        }
+    }
+    Ok(())
+}
+
+/// Hurts the players around the explosion of `flight` at `at`, the damage
+/// decreasing with the distance. Players behind a wall are shielded, and
+/// teammates of the owner are spared unless `config.teams.friendly_fire` is
+/// set. The owner itself is always hurt.
+///
+/// Like the launch, the explosion is only sent to the owner and the clients knowing it.
+fn explode(session: &mut Session,config: &Config,flight: &Flight,at: (f32,f32),out: &Outbox) -> Result<(),Box<dyn Error>> {
+    let (x, y) = at;
+    let owner = &flight.projectile.owner;
+    let msg = OutputData::Explosion { id: flight.projectile.id, x, y };
+    logic::broadcast_if(out, None, &session.players, &msg, |p| p.nickname == *owner || session.interest.knows(&p.nickname, owner))?;
+    let owner_team = session.players.get_by_nickname(owner).and_then(|i| session.players[i].team);
+    let radius = flight.weapon.projectile.as_ref().map_or(0.0, |p| p.splash).max(HIT_RADIUS);
+    let mut damages: BTreeMap<String, u32> = BTreeMap::new();
+    for player in session.players.iter().filter(|p| p.status == Status::Alive) {
+        let distance = (player.x - x).hypot(player.y - y);
+        if distance > radius || !session.map.line_of_sight(at, (player.x, player.y)) {
+            continue;
+        }
+        if player.nickname != *owner && teams::allies(owner_team, player.team) && !config.teams.friendly_fire {
+            continue;
+        }
+        let damage = flight.weapon.splash_damage_at(distance);
+        if damage > 0 {
+            damages.insert(player.nickname.clone(), damage);
+        }
+    }
+    for (target, damage) in damages {
+        logic::hurt(session, config, owner, &target, &flight.weapon.name, damage, out)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,221 @@
+use std::collections::HashMap;
+
+use crate::{data::Status, entities::{Player, Players}, n_loader::SpawnPoint, world::Map, Loader};
+use rand::prelude::*;
+
+use super::config::{SpawnConfig, SpawnStrategy};
+use super::teams;
+
+/// Distance, in tiles, under which a living player occupies a spawn point.
+const OCCUPIED_RADIUS: f32 = 1.0;
+/// Distance, in tiles, beyond which an enemy doesn't make a spawn point any less safe.
+const SAFE_DISTANCE: f32 = 20.0;
+/// Score lost by a spawn point for every enemy seeing it.
+const SIGHT_PENALTY: f32 = 10.0;
+/// Score lost by a spawn point used right now, decreasing until the reuse delay is over.
+const REUSE_PENALTY: f32 = 8.0;
+
+/// Choice of the spawn points of a session.
+///
+/// # Fields
+/// - `strategy`: How a spawn point is picked among the free ones.
+/// - `reuse_delay`: Milliseconds during which a spawn point just used is avoided.
+/// - `protection`: Milliseconds a spawned player can't be hurt.
+/// - `last_used`: Server time each spawn point was last used at, by tile.
+/// - `protected`: Server time until which each player spawned lately can't be hurt, by nickname.
+pub struct Spawns {
+    pub strategy: SpawnStrategy,
+    reuse_delay: u64,
+    protection: u64,
+    last_used: HashMap<(u8,u8), u64>,
+    protected: HashMap<String, u64>,
+}
+
+impl Spawns {
+    pub fn new(config: &SpawnConfig,strategy: SpawnStrategy) -> Self {
+        Self { strategy, reuse_delay: config.reuse_delay, protection: config.protection, last_used: HashMap::new(), protected: HashMap::new() }
+    }
+
+    /// Forgets when the spawn points were used and who is protected, for
+    /// instance after a map change.
+    pub fn reset(&mut self) {
+        self.last_used.clear();
+        self.protected.clear();
+    }
+
+    /// Puts the player named `nickname`, spawned at `now`, under spawn protection.
+    ///
+    /// # Returns
+    /// * The server time the protection ends at.
+    pub fn protect(&mut self,nickname: &str,now: u64) -> u64 {
+        let until = now + self.protection;
+        self.protected.insert(nickname.to_string(), until);
+        until
+    }
+
+    /// Whether the player named `nickname` can't be hurt at the server time `now`.
+    pub fn protected(&self,nickname: &str,now: u64) -> bool {
+        self.protected.get(nickname).is_some_and(|until| now < *until)
+    }
+
+    /// Ends the spawn protection of the player named `nickname`.
+    ///
+    /// # Returns
+    /// * `true` if it was still protected at the server time `now`.
+    pub fn unprotect(&mut self,nickname: &str,now: u64) -> bool {
+        self.protected.remove(nickname).is_some_and(|until| now < until)
+    }
+
+    /// The players still protected at the server time `now`, and until when.
+    pub fn protections(&self,now: u64) -> impl Iterator<Item = (&String, u64)> {
+        self.protected.iter().filter(move |(_, until)| now < **until).map(|(nickname, until)| (nickname, *until))
+    }
+
+    /// Center of the spawn point `player` should appear on at the server time `now`.
+    ///
+    /// Spawn points with a living player of `players` on them are never
+    /// picked. Among the free ones, those tagged with the team of the player
+    /// are preferred, then the untagged ones, then any of them. The strategy
+    /// then picks one, which is remembered as used.
+    ///
+    /// # Returns
+    /// * `None` if every spawn point is occupied, the spawn having to wait.
+    pub fn pick(&mut self,loader: &Loader,map: &Map,players: &Players,player: &Player,now: u64) -> Option<(f32,f32)> {
+        let others: Vec<&Player> = players.iter().filter(|p| p.nickname != player.nickname && p.status == Status::Alive).collect();
+        let free: Vec<&SpawnPoint> = loader.spawnpoints.iter()
+            .filter(|s| !others.iter().any(|p| (p.x - center(s).0).hypot(p.y - center(s).1) < OCCUPIED_RADIUS))
+            .collect();
+        let candidates = match player.team {
+            Some(team) => {
+                let tagged: Vec<&SpawnPoint> = free.iter().copied().filter(|s| s.team == Some(team)).collect();
+                let untagged: Vec<&SpawnPoint> = free.iter().copied().filter(|s| s.team.is_none()).collect();
+                [tagged, untagged, free].into_iter().find(|tier| !tier.is_empty())?
+            },
+            None => free,
+        };
+        let enemies: Vec<&Player> = others.into_iter().filter(|p| !teams::allies(p.team, player.team)).collect();
+        let mut rng = rand::rng();
+        let spawn = match self.strategy {
+            SpawnStrategy::Random => *candidates.choose(&mut rng)?,
+            SpawnStrategy::Farthest => *candidates.iter().max_by(|a, b| distance(a, &enemies).total_cmp(&distance(b, &enemies)))?,
+            SpawnStrategy::Scored => {
+                // A little noise spreads the players over the spawn points scored alike
+                let scored: Vec<(&SpawnPoint, f32)> = candidates.iter().map(|s| (*s, self.score(s, map, &enemies, now) + rng.random_range(0.0..1.0))).collect();
+                scored.iter().max_by(|a, b| a.1.total_cmp(&b.1))?.0
+            },
+        };
+        self.last_used.insert((spawn.x, spawn.y), now);
+        Some(center(spawn))
+    }
+
+    /// Safety of `spawn`: the distance to the closest enemy, minus a penalty
+    /// for every enemy seeing it and for a recent use.
+    fn score(&self,spawn: &SpawnPoint,map: &Map,enemies: &[&Player],now: u64) -> f32 {
+        let seen = enemies.iter().filter(|p| map.line_of_sight(center(spawn), (p.x, p.y))).count();
+        let recent = match self.last_used.get(&(spawn.x, spawn.y)) {
+            Some(time) if self.reuse_delay > 0 => 1.0 - (now.saturating_sub(*time) as f32 / self.reuse_delay as f32).min(1.0),
+            _ => 0.0,
+        };
+        distance(spawn, enemies) - SIGHT_PENALTY * seen as f32 - REUSE_PENALTY * recent
+    }
+}
+
+fn center(spawn: &SpawnPoint) -> (f32,f32) {
+    (spawn.x as f32 + 0.5, spawn.y as f32 + 0.5)
+}
+
+/// Distance from `spawn` to the closest of `enemies`, at most `SAFE_DISTANCE`.
+fn distance(spawn: &SpawnPoint,enemies: &[&Player]) -> f32 {
+    let (x, y) = center(spawn);
+    enemies.iter().map(|p| (p.x - x).hypot(p.y - y)).fold(SAFE_DISTANCE, f32::min)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::data::Team;
+    use super::*;
+
+    /// Centers of the spawn points of conf/map1.json, the first two red and the others blue.
+    const SPAWNS: [(f32,f32); 4] = [(1.5, 1.5), (1.5, 22.5), (22.5, 2.5), (22.5, 22.5)];
+
+    fn spawns(strategy: SpawnStrategy) -> (Spawns, Loader, Map) {
+        let loader = Loader::from_file("conf/map1.json").unwrap();
+        let map = Map::from(&loader);
+        (Spawns::new(&SpawnConfig::default(), strategy), loader, map)
+    }
+
+    fn player(nickname: &str,(x, y): (f32,f32),team: Option<Team>) -> Player {
+        let mut player = Player::new(nickname.to_string(), (x, y, 0.0), "goblin");
+        player.team = team;
+        player
+    }
+
+    fn players(list: Vec<Player>) -> Players {
+        let mut players = Players::new();
+        for player in list {
+            players.push(player);
+        }
+        players
+    }
+
+    #[test]
+    fn occupied_spawns_are_never_picked() {
+        let (mut spawns, loader, map) = spawns(SpawnStrategy::Random);
+        let players = players(SPAWNS[..3].iter().enumerate().map(|(i, s)| player(&format!("p{}", i), *s, None)).collect());
+        let joining = player("alice", (0.0, 0.0), None);
+        for _ in 0..10 {
+            assert_eq!(spawns.pick(&loader, &map, &players, &joining, 0), Some(SPAWNS[3]));
+        }
+    }
+
+    #[test]
+    fn nothing_is_picked_when_every_spawn_is_occupied() {
+        let (mut spawns, loader, map) = spawns(SpawnStrategy::Random);
+        let mut list: Vec<Player> = SPAWNS.iter().enumerate().map(|(i, s)| player(&format!("p{}", i), *s, None)).collect();
+        let joining = player("alice", (0.0, 0.0), None);
+        assert_eq!(spawns.pick(&loader, &map, &players(list.clone()), &joining, 0), None);
+        list[0].status = Status::Dead(0);
+        assert_eq!(spawns.pick(&loader, &map, &players(list), &joining, 0), Some(SPAWNS[0]));
+    }
+
+    #[test]
+    fn own_spawn_point_doesnt_count_as_occupied() {
+        let (mut spawns, loader, map) = spawns(SpawnStrategy::Random);
+        let list: Vec<Player> = SPAWNS.iter().enumerate().map(|(i, s)| player(&format!("p{}", i), *s, None)).collect();
+        let respawning = list[2].clone();
+        assert_eq!(spawns.pick(&loader, &map, &players(list), &respawning, 0), Some(SPAWNS[2]));
+    }
+
+    #[test]
+    fn team_spawns_are_preferred() {
+        let (mut spawns, loader, map) = spawns(SpawnStrategy::Random);
+        let joining = player("alice", (0.0, 0.0), Some(Team::Red));
+        for _ in 0..10 {
+            let picked = spawns.pick(&loader, &map, &players(vec![]), &joining, 0).unwrap();
+            assert!(SPAWNS[..2].contains(&picked));
+        }
+        let blocking = players(vec![player("bob", SPAWNS[0], Some(Team::Red)), player("carol", SPAWNS[1], Some(Team::Red))]);
+        let picked = spawns.pick(&loader, &map, &blocking, &joining, 0).unwrap();
+        assert!(SPAWNS[2..].contains(&picked));
+    }
+
+    #[test]
+    fn farthest_spawn_from_the_enemies_is_picked() {
+        let (mut spawns, loader, map) = spawns(SpawnStrategy::Farthest);
+        let enemies = players(vec![player("bob", (5.5, 5.5), None)]);
+        let joining = player("alice", (0.0, 0.0), None);
+        assert_eq!(spawns.pick(&loader, &map, &enemies, &joining, 0), Some(SPAWNS[3]));
+    }
+
+    #[test]
+    fn protection_lasts_until_it_ends_or_is_lifted() {
+        let (mut spawns, _, _) = spawns(SpawnStrategy::Random);
+        spawns.protection = 3000;
+        assert_eq!(spawns.protect("alice", 1000), 4000);
+        assert!(spawns.protected("alice", 3999));
+        assert!(!spawns.protected("alice", 4000));
+        assert!(spawns.unprotect("alice", 2000));
+        assert!(!spawns.protected("alice", 2000));
+        assert!(!spawns.unprotect("alice", 2000));
+    }
+}
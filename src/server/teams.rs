@@ -0,0 +1,62 @@
+use std::collections::BTreeMap;
+
+use crate::{data::Team, entities::Players};
+
+/// Team a joining player is put in.
+///
+/// The team asked for is granted unless it already has more players than
+/// the other one. Otherwise, the smallest team is picked.
+pub fn assign(players: &Players,wanted: Option<Team>) -> Team {
+    let sizes: BTreeMap<Team, usize> = Team::ALL.iter().map(|t| (*t, players.iter().filter(|p| p.team == Some(*t)).count())).collect();
+    let smallest = sizes.iter().min_by_key(|(_, size)| **size).map_or(Team::Red, |(team, _)| *team);
+    match wanted {
+        Some(team) if sizes[&team] <= sizes[&smallest] => team,
+        _ => smallest,
+    }
+}
+
+/// Whether `a` and `b` play in the same team.
+pub fn allies(a: Option<Team>,b: Option<Team>) -> bool {
+    a.is_some() && a == b
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::entities::Player;
+    use super::*;
+
+    fn players(teams: &[Team]) -> Players {
+        let mut players = Players::new();
+        for (i, team) in teams.iter().enumerate() {
+            let mut player = Player::new(format!("player{}", i), (1.5, 1.5, 0.0), "goblin");
+            player.team = Some(*team);
+            players.push(player);
+        }
+        players
+    }
+
+    #[test]
+    fn wanted_team_is_granted_while_balanced() {
+        assert_eq!(assign(&players(&[]), Some(Team::Blue)), Team::Blue);
+        assert_eq!(assign(&players(&[Team::Red]), Some(Team::Blue)), Team::Blue);
+    }
+
+    #[test]
+    fn wanted_team_is_refused_when_bigger() {
+        assert_eq!(assign(&players(&[Team::Red]), Some(Team::Red)), Team::Blue);
+        assert_eq!(assign(&players(&[Team::Blue, Team::Blue, Team::Red]), Some(Team::Blue)), Team::Red);
+    }
+
+    #[test]
+    fn smallest_team_is_picked_without_a_wish() {
+        assert_eq!(assign(&players(&[]), None), Team::Red);
+        assert_eq!(assign(&players(&[Team::Red, Team::Red, Team::Blue]), None), Team::Blue);
+    }
+
+    #[test]
+    fn only_teammates_are_allies() {
+        assert!(allies(Some(Team::Red), Some(Team::Red)));
+        assert!(!allies(Some(Team::Red), Some(Team::Blue)));
+        assert!(!allies(None, None));
+    }
+}
@@ -0,0 +1,92 @@
+use std::{error::Error, net::SocketAddr};
+
+use crate::{data::{Loadout, OutputData}, entities::Weapon};
+
+use super::net::Outbox;
+use super::logic::{self, Session};
+
+/// Brings `loadout` back to the starting weapons with full magazines,
+/// keeping the weapon in hand if it is one of them.
+pub fn refill(loadout: &mut Loadout,weapons: &[Weapon]) {
+    let weapon = loadout.weapon;
+    *loadout = Loadout::new(weapons);
+    if loadout.owns(weapon) {
+        loadout.weapon = weapon;
+    }
+}
+
+/// Sends the player of `index` its loadout.
+pub fn send(session: &Session,index: usize,out: &Outbox) -> Result<(),Box<dyn Error>> {
+    let player = &session.players[index];
+    logic::send_to_player(out, player, &OutputData::Loadout(player.loadout.clone()))
+}
+
+/// Takes a shot from the magazine of the weapon in hand of the player of `index`.
+///
+/// The shot is refused while the previous one or a reload isn't over, the
+/// client being sent its loadout to correct its own. An emptied magazine is
+/// reloaded right away.
+///
+/// # Returns
+/// * `Ok(Some(Weapon))` - The weapon fired.
+/// * `Ok(None)` if the shot is refused.
+pub fn fire(session: &mut Session,index: usize,out: &Outbox) -> Result<Option<Weapon>,Box<dyn Error>> {
+    let now = session.now_ms();
+    let player = &mut session.players.players[index];
+    let loadout = &mut player.loadout;
+    let weapon = match session.weapons.get(loadout.weapon) {
+        Some(w) => w.clone(),
+        None => return Err(format!("{} holds no weapon", player.nickname).into()),
+    };
+    if loadout.ready_at > now || loadout.ammo() == 0 {
+        debug!("{} fired too early with the {}, shot refused", player.nickname, weapon.name);
+        send(session, index, out)?;
+        return Ok(None);
+    }
+    loadout.give(loadout.weapon, loadout.ammo() - 1);
+    loadout.ready_at = now + weapon.fire_interval();
+    if loadout.ammo() == 0 {
+        loadout.give(loadout.weapon, weapon.ammo);
+        loadout.ready_at = now + weapon.reload;
+    }
+    Ok(Some(weapon))
+}
+
+/// Puts in hand the weapon of index `weapon` for the player of `addr`, if it
+/// holds it.
+///
+/// Switching doesn't shorten the wait after the last shot or reload.
+pub fn switch(session: &mut Session,addr: SocketAddr,weapon: usize,out: &Outbox) -> Result<(),Box<dyn Error>> {
+    let index = match session.players.get_by_addr(&addr) {
+        Some(i) => i,
+        None => return Err(format!("weapon switch from unknown address {}", addr).into()),
+    };
+    if weapon >= session.weapons.len() {
+        return Err(format!("{} asked for the unknown weapon {}", session.players[index].nickname, weapon).into());
+    }
+    let loadout = &mut session.players.players[index].loadout;
+    if loadout.owns(weapon) {
+        loadout.weapon = weapon;
+    }
+    send(session, index, out)
+}
+
+/// Refills the magazine of the weapon in hand of the player of `addr`, which
+/// can't fire until the reload time of the weapon is over.
+pub fn reload(session: &mut Session,addr: SocketAddr,out: &Outbox) -> Result<(),Box<dyn Error>> {
+    let index = match session.players.get_by_addr(&addr) {
+        Some(i) => i,
+        None => return Err(format!("reload from unknown address {}", addr).into()),
+    };
+    let now = session.now_ms();
+    let loadout = &mut session.players.players[index].loadout;
+    let weapon = match session.weapons.get(loadout.weapon) {
+        Some(w) => w,
+        None => return Ok(()),
+    };
+    if loadout.ammo() < weapon.ammo {
+        loadout.give(loadout.weapon, weapon.ammo);
+        loadout.ready_at = loadout.ready_at.max(now) + weapon.reload;
+    }
+    send(session, index, out)
+}
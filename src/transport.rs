@@ -0,0 +1,167 @@
+use std::{
+    collections::HashMap,
+    io,
+    net::{Ipv4Addr, SocketAddr, UdpSocket},
+    sync::{mpsc::{channel, Receiver, RecvTimeoutError, Sender}, Arc, Mutex},
+    time::Duration,
+};
+
+/// Largest payload of a UDP datagram. The messages of the server, a
+/// `Connecting` carrying the map and the visible players above all, are read
+/// with buffers of this size.
+pub const MAX_DATAGRAM: usize = 65507;
+
+/// A datagram socket the server and the clients exchange their messages on.
+///
+/// It mirrors the part of `UdpSocket` the game uses, so the same code can run
+/// over the network or over a `MemoryNetwork` inside a single process.
+pub trait Transport: Send + Sync {
+    /// Sends one datagram to `addr`.
+    fn send_to(&self, buf: &[u8], addr: SocketAddr) -> io::Result<usize>;
+
+    /// Waits for the next datagram and returns its size and sender.
+    ///
+    /// # Returns
+    /// * `Err` of kind `WouldBlock` if nothing arrived before the read timeout.
+    fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)>;
+
+    /// Sets how long `recv_from` waits, forever if `None`.
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()>;
+
+    fn local_addr(&self) -> io::Result<SocketAddr>;
+}
+
+impl Transport for UdpSocket {
+    fn send_to(&self, buf: &[u8], addr: SocketAddr) -> io::Result<usize> {
+        UdpSocket::send_to(self, buf, addr)
+    }
+
+    fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        UdpSocket::recv_from(self, buf)
+    }
+
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        UdpSocket::set_read_timeout(self, timeout)
+    }
+
+    fn local_addr(&self) -> io::Result<SocketAddr> {
+        UdpSocket::local_addr(self)
+    }
+}
+
+type Datagram = (Vec<u8>, SocketAddr);
+
+#[derive(Default)]
+struct Routes {
+    sockets: HashMap<SocketAddr, Sender<Datagram>>,
+    next_port: u16,
+}
+
+#[derive(Clone, Default)]
+/// An in-process network delivering datagrams between `MemorySocket`s through channels.
+///
+/// Nothing is ever lost or reordered, so a server and several clients can
+/// run deterministically in the same process. Clones share the same network.
+///
+/// # Example
+/// ```
+/// use multiplayer_fps::transport::{MemoryNetwork, Transport};
+///
+/// let network = MemoryNetwork::new();
+/// let server = network.bind_any().unwrap();
+/// let client = network.bind_any().unwrap();
+/// client.send_to(b"hello", server.local_addr().unwrap()).unwrap();
+/// let mut buf = [0; 16];
+/// let (size, from) = server.recv_from(&mut buf).unwrap();
+/// assert_eq!((&buf[..size], from), (&b"hello"[..], client.local_addr().unwrap()));
+/// ```
+pub struct MemoryNetwork {
+    routes: Arc<Mutex<Routes>>,
+}
+
+impl MemoryNetwork {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Opens a socket on `addr`, or on a free port of its ip if its port is 0.
+    ///
+    /// # Returns
+    /// * `Err` of kind `AddrInUse` if another socket of the network has the address.
+    pub fn bind(&self, mut addr: SocketAddr) -> io::Result<MemorySocket> {
+        let mut routes = self.routes.lock().unwrap_or_else(|e| e.into_inner());
+        if addr.port() == 0 {
+            loop {
+                routes.next_port = routes.next_port.wrapping_add(1).max(1);
+                addr.set_port(routes.next_port);
+                if !routes.sockets.contains_key(&addr) {
+                    break;
+                }
+            }
+        } else if routes.sockets.contains_key(&addr) {
+            return Err(io::Error::new(io::ErrorKind::AddrInUse, format!("{} is already bound", addr)));
+        }
+        let (tx, rx) = channel();
+        routes.sockets.insert(addr, tx);
+        Ok(MemorySocket { addr, network: self.clone(), rx: Mutex::new(rx), timeout: Mutex::new(None) })
+    }
+
+    /// Opens a socket on a free port of `127.0.0.1`.
+    pub fn bind_any(&self) -> io::Result<MemorySocket> {
+        self.bind(SocketAddr::from((Ipv4Addr::LOCALHOST, 0)))
+    }
+}
+
+/// A socket of a `MemoryNetwork`, closed when dropped.
+pub struct MemorySocket {
+    addr: SocketAddr,
+    network: MemoryNetwork,
+    rx: Mutex<Receiver<Datagram>>,
+    timeout: Mutex<Option<Duration>>,
+}
+
+impl Transport for MemorySocket {
+    /// Datagrams sent to an address nobody is bound to are dropped, as with UDP.
+    fn send_to(&self, buf: &[u8], addr: SocketAddr) -> io::Result<usize> {
+        let routes = self.network.routes.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(tx) = routes.sockets.get(&addr) {
+            let _ = tx.send((buf.to_vec(), self.addr));
+        }
+        Ok(buf.len())
+    }
+
+    /// Datagrams longer than `buf` are truncated, as with UDP.
+    fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        let timeout = *self.timeout.lock().unwrap_or_else(|e| e.into_inner());
+        let rx = self.rx.lock().unwrap_or_else(|e| e.into_inner());
+        let (data, from) = match timeout {
+            Some(t) => rx.recv_timeout(t).map_err(|e| match e {
+                RecvTimeoutError::Timeout => io::Error::from(io::ErrorKind::WouldBlock),
+                RecvTimeoutError::Disconnected => io::Error::from(io::ErrorKind::NotConnected),
+            })?,
+            None => rx.recv().map_err(|_| io::Error::from(io::ErrorKind::NotConnected))?,
+        };
+        let size = data.len().min(buf.len());
+        buf[..size].copy_from_slice(&data[..size]);
+        Ok((size, from))
+    }
+
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        if timeout == Some(Duration::ZERO) {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "cannot set a 0 duration timeout"));
+        }
+        *self.timeout.lock().unwrap_or_else(|e| e.into_inner()) = timeout;
+        Ok(())
+    }
+
+    fn local_addr(&self) -> io::Result<SocketAddr> {
+        Ok(self.addr)
+    }
+}
+
+impl Drop for MemorySocket {
+    fn drop(&mut self) {
+        let mut routes = self.network.routes.lock().unwrap_or_else(|e| e.into_inner());
+        routes.sockets.remove(&self.addr);
+    }
+}
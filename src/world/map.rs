@@ -16,12 +16,7 @@ impl Map {
     }
 
     pub fn get_tile(&self,x: i32,y:i32) -> Option<Tile> {
-        for t in self.layout.clone() {
-            if (t.pos.x,t.pos.y) == (x,y) {
-                return Some(t);
-            }
-        }
-        None
+        self.layout.iter().find(|t| (t.pos.x,t.pos.y) == (x,y)).copied()
     }
 
     pub fn is_wall(&self,x:i32,y:i32) -> Option<bool> {
@@ -36,4 +31,111 @@ impl Map {
         let t = self.get_tile(x, y)?;
         t.get_tvalue()
     }
- }
\ No newline at end of file
+
+    /// Uses DDA raycasting to check that no wall stands between two points.
+    ///
+    /// # Returns
+    /// * `true` if `to` can be seen from `from`.
+    pub fn line_of_sight(&self,from: (f32,f32),to: (f32,f32)) -> bool {
+        let (start_x, start_y) = from;
+        let (end_x, end_y) = to;
+
+        let dir_x = end_x - start_x;
+        let dir_y = end_y - start_y;
+
+        let ray_len = (dir_x * dir_x + dir_y * dir_y).sqrt();
+        if ray_len == 0.0 {
+            return true; // Same position
+        }
+
+        let raydir_x = dir_x / ray_len;
+        let raydir_y = dir_y / ray_len;
+
+        let mut map_x = start_x.floor() as i32;
+        let mut map_y = start_y.floor() as i32;
+
+        let delta_dist_x = if raydir_x != 0.0 { (1.0 / raydir_x).abs() } else { f32::INFINITY };
+        let delta_dist_y = if raydir_y != 0.0 { (1.0 / raydir_y).abs() } else { f32::INFINITY };
+
+        let (step_x, mut side_dist_x) = if raydir_x < 0.0 {
+            (-1, (start_x - map_x as f32) * delta_dist_x)
+        } else {
+            (1, (map_x as f32 + 1.0 - start_x) * delta_dist_x)
+        };
+
+        let (step_y, mut side_dist_y) = if raydir_y < 0.0 {
+            (-1, (start_y - map_y as f32) * delta_dist_y)
+        } else {
+            (1, (map_y as f32 + 1.0 - start_y) * delta_dist_y)
+        };
+
+        let mut dist_traveled = 0.0;
+
+        // DDA loop
+        while dist_traveled < ray_len {
+            if side_dist_x < side_dist_y {
+                map_x += step_x;
+                dist_traveled = side_dist_x;
+                side_dist_x += delta_dist_x;
+            } else {
+                map_y += step_y;
+                dist_traveled = side_dist_y;
+                side_dist_y += delta_dist_y;
+            }
+
+            if dist_traveled < ray_len {
+                if let Some(true) = self.is_wall(map_x, map_y) {
+                    return false; // Wall detected
+                }
+            }
+        }
+
+        true // No wall detected
+    }
+ }
+
+#[cfg(test)]
+impl Map {
+    /// A map drawn row after row, `#` being a wall and `.` the floor.
+    pub fn from_rows(rows: &[&str]) -> Self {
+        let layout = rows.iter().enumerate()
+            .flat_map(|(y, row)| row.chars().enumerate().map(move |(x, c)| Tile::new(sdl2::rect::Point::new(x as i32, y as i32), (c == '#') as u8)))
+            .collect();
+        Self::new(layout, HashMap::new(), "placeholder".to_string(), Vec::new())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Map;
+
+    fn pillar() -> Map {
+        Map::from_rows(&[
+            "#####",
+            "#...#",
+            "#.#.#",
+            "#...#",
+            "#####",
+        ])
+    }
+
+    #[test]
+    fn line_of_sight_along_the_floor() {
+        let map = pillar();
+        assert!(map.line_of_sight((1.5, 1.5), (3.5, 1.5)));
+        assert!(map.line_of_sight((1.5, 1.5), (1.5, 3.5)));
+        assert!(map.line_of_sight((3.5, 3.5), (1.5, 3.5)));
+    }
+
+    #[test]
+    fn line_of_sight_blocked_by_a_wall() {
+        let map = pillar();
+        assert!(!map.line_of_sight((1.5, 2.5), (3.5, 2.5)));
+        assert!(!map.line_of_sight((2.5, 3.5), (2.5, 1.5)));
+    }
+
+    #[test]
+    fn line_of_sight_to_the_same_point() {
+        assert!(pillar().line_of_sight((1.5, 1.5), (1.5, 1.5)));
+    }
+}
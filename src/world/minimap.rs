@@ -17,18 +17,29 @@ pub struct Minimap {
     target_color: Option<Color>,
 
     others_color: Color,
-    others: Vec<FPoint>
+    others: Vec<(FPoint, Color)>,
+    markers: Vec<(FPoint, Color)>
 }
 
 impl Minimap {
     pub fn new(map: &Map,target: &FPoint, wall_color: Color, bg_color: Color) -> Self {
-        Self { map: map.clone(), target: target.clone(), wall_size: DEFAULT_WALL_SIZE, wall_color: wall_color.clone(), bg_color: bg_color.clone(), target_color: None,others:Vec::new(),others_color:Color::RED }
+        Self { map: map.clone(), target: target.clone(), wall_size: DEFAULT_WALL_SIZE, wall_color: wall_color.clone(), bg_color: bg_color.clone(), target_color: None,others:Vec::new(),others_color:Color::RED,markers:Vec::new() }
     }
 
     pub fn set_others(&mut self,vec: Vec<FPoint>) {
+        self.others = vec.into_iter().map(|p| (p, self.others_color)).collect()
+    }
+
+    /// Same as `set_others`, each point being drawn with its own color.
+    pub fn set_colored_others(&mut self,vec: Vec<(FPoint, Color)>) {
         self.others = vec
     }
 
+    /// Points of interest, such as flags, drawn as outlined tiles under the players.
+    pub fn set_markers(&mut self,vec: Vec<(FPoint, Color)>) {
+        self.markers = vec
+    }
+
     pub fn set_target_pinpoint(&mut self,color: Option<Color>) {
         self.target_color = color
     }
@@ -74,6 +85,18 @@ impl display::Display for Minimap {
             }
         }
 
+        // marqueurs
+        for (marker, color) in &self.markers {
+            canvas.set_draw_color(*color);
+            let rect = sdl2::rect::Rect::new(
+                (marker.x * self.wall_size as f32) as i32 + offset_x - (self.wall_size as i32 / 2),
+                (marker.y * self.wall_size as f32) as i32 + offset_y - (self.wall_size as i32 / 2),
+                self.wall_size as u32,
+                self.wall_size as u32
+            );
+            canvas.draw_rect(rect)?;
+        }
+
         // target au centre
         if let Some(color) = self.target_color {
             let rect = sdl2::rect::Rect::new(
@@ -87,8 +110,8 @@ impl display::Display for Minimap {
         }
 
         // affichage des autres
-        canvas.set_draw_color(self.others_color);
-        for other in &self.others {
+        for (other, color) in &self.others {
+            canvas.set_draw_color(*color);
             let other_px_x = (other.x * self.wall_size as f32) as i32 + offset_x;
             let other_px_y = (other.y * self.wall_size as f32) as i32 + offset_y;
 
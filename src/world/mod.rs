@@ -1,4 +1,5 @@
 mod map;
+mod path;
 mod tiles;
 
 pub use map::Map;
@@ -0,0 +1,104 @@
+use std::collections::{HashMap, VecDeque};
+
+use super::Map;
+
+impl Map {
+    /// Finds the shortest walk between two tiles with a breadth-first search.
+    ///
+    /// Moves are horizontal or vertical, walls and tiles outside the map are
+    /// never crossed.
+    ///
+    /// # Returns
+    /// * `Some(path)` - The tiles to walk through, `to` included and `from` excluded.
+    /// * `None` if `to` can't be reached.
+    pub fn find_path(&self,from: (i32,i32),to: (i32,i32)) -> Option<Vec<(i32,i32)>> {
+        if self.is_wall(to.0, to.1) != Some(false) {
+            return None;
+        }
+        let mut previous: HashMap<(i32,i32),(i32,i32)> = HashMap::new();
+        let mut queue = VecDeque::from([from]);
+        previous.insert(from, from);
+        while let Some(tile) = queue.pop_front() {
+            if tile == to {
+                let mut path = vec![to];
+                let mut current = to;
+                while previous[&current] != from {
+                    current = previous[&current];
+                    path.push(current);
+                }
+                if from == to {
+                    path.clear();
+                }
+                path.reverse();
+                return Some(path);
+            }
+            for (dx, dy) in [(1, 0), (-1, 0), (0, 1), (0, -1)] {
+                let next = (tile.0 + dx, tile.1 + dy);
+                if previous.contains_key(&next) || self.is_wall(next.0, next.1) != Some(false) {
+                    continue;
+                }
+                previous.insert(next, tile);
+                queue.push_back(next);
+            }
+        }
+        None
+    }
+
+    /// Positions of every tile that can be walked on.
+    pub fn walkable_tiles(&self) -> Vec<(i32,i32)> {
+        self.layout.iter()
+            .filter(|t| !t.is_wall())
+            .map(|t| (t.pos.x, t.pos.y))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Map;
+
+    fn corridor() -> Map {
+        Map::from_rows(&[
+            "#####",
+            "#...#",
+            "###.#",
+            "#...#",
+            "#####",
+        ])
+    }
+
+    #[test]
+    fn find_path_goes_around_the_walls() {
+        let path = corridor().find_path((1, 1), (1, 3)).unwrap();
+        assert_eq!(path, vec![(2, 1), (3, 1), (3, 2), (3, 3), (2, 3), (1, 3)]);
+    }
+
+    #[test]
+    fn find_path_to_the_start_is_empty() {
+        assert_eq!(corridor().find_path((1, 1), (1, 1)), Some(Vec::new()));
+    }
+
+    #[test]
+    fn find_path_never_ends_in_a_wall_or_outside() {
+        let map = corridor();
+        assert_eq!(map.find_path((1, 1), (2, 2)), None);
+        assert_eq!(map.find_path((1, 1), (9, 9)), None);
+    }
+
+    #[test]
+    fn find_path_fails_on_a_closed_room() {
+        let map = Map::from_rows(&[
+            "#####",
+            "#.#.#",
+            "#####",
+        ]);
+        assert_eq!(map.find_path((1, 1), (3, 1)), None);
+    }
+
+    #[test]
+    fn walkable_tiles_are_the_floor() {
+        let mut tiles = corridor().walkable_tiles();
+        tiles.sort();
+        assert_eq!(tiles, vec![(1, 1), (1, 3), (2, 1), (2, 3), (3, 1), (3, 2), (3, 3)]);
+    }
+}
@@ -0,0 +1,173 @@
+use std::{net::SocketAddr, sync::mpsc::{channel, Receiver}, time::Duration};
+use multiplayer_fps::data::{default_addr, Connection, GameEvent, InputData, OutputData, Phase, Status, Update};
+use multiplayer_fps::server::{clock::Clock, config::Config, logic::{self, Session}, net::{Outbox, Outgoing}};
+use multiplayer_fps::transport::{MemoryNetwork, MemorySocket, Transport};
+
+/// Longest wait for a datagram. Datagrams are delivered as soon as they are
+/// sent, so this only bounds the check of an empty socket.
+const EMPTY_CHECK: Duration = Duration::from_millis(1);
+
+/// A session stepped by hand, its time only moving with `tick`.
+struct Server {
+    session: Session,
+    config: Config,
+    out: Outbox,
+    outgoing: Receiver<Outgoing>,
+    socket: MemorySocket,
+}
+
+impl Server {
+    fn new(network: &MemoryNetwork,config: Config) -> Self {
+        let socket = network.bind_any().unwrap();
+        socket.set_read_timeout(Some(EMPTY_CHECK)).unwrap();
+        let (tx, outgoing) = channel();
+        let session = Session::new(&config, Clock::manual()).unwrap();
+        Self { session, config, out: Outbox::new(tx), outgoing, socket }
+    }
+
+    fn addr(&self) -> SocketAddr {
+        self.socket.local_addr().unwrap()
+    }
+
+    /// Handles every packet waiting on the socket, then sends the answers.
+    fn step(&mut self) {
+        loop {
+            let (data, size) = InputData::receive(&self.socket).unwrap();
+            if matches!(data, InputData::None) {
+                break;
+            }
+            logic::handle(&mut self.session, &self.out, &self.config, data, size).unwrap();
+        }
+        self.flush();
+    }
+
+    /// Moves the clock forward by `elapsed` and runs a tick.
+    fn tick(&mut self,elapsed: Duration) {
+        self.session.clock.advance(elapsed);
+        logic::tick(&mut self.session, &self.out, &self.config).unwrap();
+        self.flush();
+    }
+
+    fn flush(&self) {
+        for Outgoing { to, msg } in self.outgoing.try_iter() {
+            let serialized = serde_json::to_string(&msg).unwrap();
+            for addr in to {
+                self.socket.send_to(serialized.as_bytes(), addr).unwrap();
+            }
+        }
+    }
+}
+
+struct Client {
+    socket: MemorySocket,
+    server: SocketAddr,
+}
+
+impl Client {
+    fn new(network: &MemoryNetwork,server: SocketAddr) -> Self {
+        let socket = network.bind_any().unwrap();
+        socket.set_read_timeout(Some(EMPTY_CHECK)).unwrap();
+        Self { socket, server }
+    }
+
+    fn send(&self,data: &InputData) {
+        self.socket.send_to(serde_json::to_string(data).unwrap().as_bytes(), self.server).unwrap();
+    }
+
+    fn connect(&self,nickname: &str) {
+        self.send(&InputData::Connection(Connection { addr: default_addr(), nickname: nickname.to_string(), password: None, team: None, skin: None }));
+    }
+
+    /// Every message received since the last call.
+    fn received(&self) -> Vec<OutputData> {
+        let mut messages = Vec::new();
+        loop {
+            match OutputData::parse(&self.socket).unwrap() {
+                OutputData::None => return messages,
+                msg => messages.push(msg),
+            }
+        }
+    }
+}
+
+fn config() -> Config {
+    let mut config = Config { map: Some("conf/map1.json".to_string()), ..Config::default() };
+    config.interest.enabled = false;
+    config
+}
+
+/// Connects a client for each nickname, every one of them being accepted.
+fn join(network: &MemoryNetwork,server: &mut Server,nicknames: &[&str]) -> Vec<Client> {
+    let mut clients = Vec::new();
+    for nickname in nicknames {
+        let client = Client::new(network, server.addr());
+        client.connect(nickname);
+        server.step();
+        let accepted = client.received().into_iter().find_map(|msg| match msg {
+            OutputData::Connecting((player, _, _)) => Some(player.nickname),
+            _ => None,
+        });
+        assert_eq!(accepted.as_deref(), Some(*nickname));
+        clients.push(client);
+    }
+    server.tick(Duration::ZERO);
+    clients
+}
+
+#[test]
+fn clients_see_each_other_move_and_leave() {
+    let network = MemoryNetwork::new();
+    let mut server = Server::new(&network, config());
+    let clients = join(&network, &mut server, &["alice", "bob", "carol"]);
+    for client in &clients {
+        client.received();
+    }
+
+    let alice = &server.session.players[server.session.players.get_by_nickname(&"alice").unwrap()];
+    let moved = (alice.x, alice.y, alice.d + 0.5);
+    clients[0].send(&InputData::Update(Update::new(default_addr(), "alice".to_string(), moved)));
+    server.step();
+    for client in &clients[1..] {
+        let seen = client.received().into_iter().any(|msg| matches!(msg, OutputData::Update(u) if u.nickname == "alice" && u.d == Some(moved.2)));
+        assert!(seen);
+    }
+    assert!(!clients[0].received().iter().any(|msg| matches!(msg, OutputData::Update(_))));
+
+    clients[2].send(&InputData::Disconnection { addr: default_addr() });
+    server.step();
+    assert_eq!(server.session.players.len(), 2);
+    for client in &clients[..2] {
+        let received = client.received();
+        assert!(received.iter().any(|msg| matches!(msg, OutputData::Update(u) if u.nickname == "carol" && u.status == Some(Status::Disconnecting))));
+        assert!(received.iter().any(|msg| matches!(msg, OutputData::Event(GameEvent::Leave(nickname)) if nickname == "carol")));
+    }
+}
+
+#[test]
+fn silent_clients_time_out() {
+    let network = MemoryNetwork::new();
+    let mut server = Server::new(&network, config());
+    let clients = join(&network, &mut server, &["alice"]);
+    clients[0].received();
+
+    let idle = Duration::from_secs(server.config.timeouts.idle);
+    server.tick(idle);
+    assert_eq!(server.session.players.len(), 1);
+    server.tick(Duration::from_secs(1));
+    assert!(server.session.players.is_empty());
+    assert!(clients[0].received().iter().any(|msg| matches!(msg, OutputData::AccessDeny(deny) if deny.reason == "timed out")));
+}
+
+#[test]
+fn match_starts_after_the_warmup() {
+    let network = MemoryNetwork::new();
+    let mut server = Server::new(&network, config());
+    let clients = join(&network, &mut server, &["alice"]);
+    let warmup = Duration::from_secs(server.config.match_rules.warmup);
+    assert_eq!(server.session.phase, Phase::Warmup { until: Some(warmup.as_millis() as u64) });
+    clients[0].received();
+
+    server.tick(warmup);
+    assert!(matches!(server.session.phase, Phase::InProgress { .. }));
+    assert!(clients[0].received().iter().any(|msg| matches!(msg, OutputData::Phase(Phase::InProgress { .. }))));
+}